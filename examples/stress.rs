@@ -0,0 +1,138 @@
+//! A multi-threaded stress test for `FreezeBox` and `MaybeBox`.
+//!
+//! Many threads repeatedly race to initialize and read a batch of shared
+//! containers, and the main thread then consumes them. Every value is
+//! checked, so memory-ordering or ownership bugs show up as assertion
+//! failures (or as errors when run under a sanitizer or Miri).
+//!
+//! Usage: `cargo run --release --example stress [SECONDS] [THREADS]`
+//!
+//! To run with AddressSanitizer on nightly:
+//! `RUSTFLAGS=-Zsanitizer=address cargo +nightly run --example stress --target <host>`
+
+use freezebox::{FreezeBox, MaybeBox};
+use std::env;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The number of containers of each type shared in one round.
+const SLOTS: usize = 64;
+
+/// One round's worth of shared containers.
+struct Round {
+    freeze: Vec<FreezeBox<Vec<u64>>>,
+    maybe: Vec<MaybeBox<Vec<u64>>>,
+}
+
+impl Round {
+    fn new() -> Self {
+        Self {
+            freeze: (0..SLOTS).map(|_| FreezeBox::default()).collect(),
+            maybe: (0..SLOTS).map(|_| MaybeBox::default()).collect(),
+        }
+    }
+}
+
+/// The value that belongs in a slot, which every thread can compute.
+///
+/// Using a heap-allocated value means that a reader who observes a
+/// partially-published value is likely to crash or fail the check.
+fn expected(round: u64, slot: usize) -> Vec<u64> {
+    (0..8)
+        .map(|i| round * 1000 + slot as u64 * 10 + i)
+        .collect()
+}
+
+fn check(val: &[u64], round: u64, slot: usize) {
+    assert_eq!(
+        val,
+        &expected(round, slot)[..],
+        "round {} slot {}",
+        round,
+        slot
+    );
+}
+
+fn worker(id: usize, shared: &Mutex<Option<(u64, Arc<Round>)>>, barrier: &Barrier) {
+    loop {
+        // Wait for the main thread to publish a new round.
+        barrier.wait();
+        let (round_num, round) = match &*shared.lock().unwrap() {
+            Some((n, round)) => (*n, Arc::clone(round)),
+            None => return,
+        };
+
+        // Visit the slots in a different order on each thread, so that
+        // initializers and readers collide as much as possible.
+        for i in 0..SLOTS {
+            let slot = (i * (id + 1) + id) % SLOTS;
+            if (id + i) % 3 == 0 {
+                // All initializers store an equal value, so the race is
+                // allowed and no thread panics.
+                let val = round.freeze[slot].lazy_init_idempotent(expected(round_num, slot));
+                check(val, round_num, slot);
+                let val = round.maybe[slot].lazy_init_idempotent(expected(round_num, slot));
+                check(val, round_num, slot);
+            } else {
+                if round.freeze[slot].is_initialized() {
+                    check(&round.freeze[slot], round_num, slot);
+                }
+                if let Some(val) = round.maybe[slot].get() {
+                    check(val, round_num, slot);
+                }
+            }
+        }
+        drop(round);
+
+        // Let the main thread know that we're done with this round.
+        barrier.wait();
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let seconds: u64 = args.next().map_or(5, |s| s.parse().expect("bad SECONDS"));
+    let threads: usize = args.next().map_or(32, |s| s.parse().expect("bad THREADS"));
+
+    let shared = Arc::new(Mutex::new(None));
+    let barrier = Arc::new(Barrier::new(threads + 1));
+    let handles: Vec<_> = (0..threads)
+        .map(|id| {
+            let shared = Arc::clone(&shared);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || worker(id, &shared, &barrier))
+        })
+        .collect();
+
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let mut rounds = 0;
+    while Instant::now() < deadline {
+        rounds += 1;
+        *shared.lock().unwrap() = Some((rounds, Arc::new(Round::new())));
+        barrier.wait();
+        barrier.wait();
+
+        // Every worker has dropped its reference, so we can consume the
+        // containers and check whatever they hold.
+        let (round_num, round) = shared.lock().unwrap().take().unwrap();
+        let round = Arc::try_unwrap(round).ok().expect("round still shared");
+        for (slot, fb) in round.freeze.into_iter().enumerate() {
+            if let Some(val) = fb.into_inner() {
+                check(&val, round_num, slot);
+            }
+        }
+        for (slot, mb) in round.maybe.into_iter().enumerate() {
+            if let Some(val) = mb.into_inner() {
+                check(&val, round_num, slot);
+            }
+        }
+    }
+
+    // Tell the workers to exit.
+    barrier.wait();
+    for h in handles {
+        h.join().unwrap();
+    }
+    println!("{} rounds on {} threads: ok", rounds, threads);
+}
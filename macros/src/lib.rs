@@ -0,0 +1,339 @@
+//! Procedural macros for the [`freezebox`] crate.
+//!
+//! These macros are re-exported by `freezebox` when its `macros` feature
+//! is enabled; there's no need to depend on this crate directly.
+//!
+//! [`freezebox`]: https://docs.rs/freezebox
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Turn struct fields into late-initialized fields with typed accessors.
+///
+/// Apply `#[late_init]` to a struct with named fields, and mark each field
+/// that should be late-initialized with `#[late_init]`. Each marked field
+/// `foo: T` is rewritten into a private `foo: FreezeBox<T>`, and two
+/// accessors are generated, with the field's original visibility:
+///
+/// * `fn foo(&self) -> &T`, which panics if `foo` is uninitialized.
+/// * `fn set_foo(&self, val: T)`, which panics if `foo` is already
+///   initialized.
+///
+/// Marking a field with `#[late_init(maybe)]` uses a `MaybeBox<T>`
+/// instead, and the getter returns `Option<&T>`.
+///
+/// Because the containers are hidden, the struct's public API only
+/// mentions the field types.
+///
+/// # Examples
+/// ```
+/// use freezebox::late_init;
+///
+/// #[late_init]
+/// #[derive(Default)]
+/// pub struct Resources {
+///     #[late_init]
+///     pub name: String,
+///     #[late_init(maybe)]
+///     pub nickname: String,
+///     pub id: u32,
+/// }
+///
+/// let res = Resources::default();
+/// res.set_name("Hello!".to_string());
+/// assert_eq!(res.name(), "Hello!");
+/// assert_eq!(res.nickname(), None);
+/// assert_eq!(res.id, 0);
+/// ```
+#[proc_macro_attribute]
+pub fn late_init(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return compile_error("`#[late_init]` on a struct takes no arguments");
+    }
+    match expand(item) {
+        Ok(output) => output,
+        Err(msg) => compile_error(msg),
+    }
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({:?});", msg)
+        .parse()
+        .expect("failed to parse compile_error")
+}
+
+/// Which container a late-initialized field uses.
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Freeze,
+    Maybe,
+}
+
+/// A parsed struct field.
+struct Field {
+    /// Attributes, other than `#[late_init]`.
+    attrs: String,
+    vis: String,
+    name: String,
+    ty: String,
+    late: Option<Container>,
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    match tt {
+        TokenTree::Punct(p) => p.as_char() == ch,
+        _ => false,
+    }
+}
+
+fn is_ident(tt: &TokenTree, name: &str) -> bool {
+    match tt {
+        TokenTree::Ident(i) => i.to_string() == name,
+        _ => false,
+    }
+}
+
+fn to_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+/// Split tokens on commas that aren't nested inside angle brackets.
+///
+/// Other brackets are already grouped by the tokenizer, but angle
+/// brackets are plain punctuation, so we have to track them ourselves.
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, tt) in tokens.iter().enumerate() {
+        if is_punct(tt, '<') {
+            depth += 1;
+        } else if is_punct(tt, '>') && !(i > 0 && is_punct(&tokens[i - 1], '-')) {
+            depth = depth.saturating_sub(1);
+        } else if is_punct(tt, ',') && depth == 0 {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+/// Consume leading `#[...]` attributes.
+fn take_attrs(tokens: &[TokenTree]) -> (Vec<&[TokenTree]>, &[TokenTree]) {
+    let mut attrs = Vec::new();
+    let mut rest = tokens;
+    while rest.len() >= 2 && is_punct(&rest[0], '#') {
+        match &rest[1] {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => {
+                attrs.push(&rest[..2]);
+                rest = &rest[2..];
+            }
+            _ => break,
+        }
+    }
+    (attrs, rest)
+}
+
+/// Consume a leading visibility, like `pub` or `pub(crate)`.
+fn take_vis(tokens: &[TokenTree]) -> (&[TokenTree], &[TokenTree]) {
+    if tokens.first().map_or(false, |tt| is_ident(tt, "pub")) {
+        match tokens.get(1) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                tokens.split_at(2)
+            }
+            _ => tokens.split_at(1),
+        }
+    } else {
+        tokens.split_at(0)
+    }
+}
+
+/// Check whether an attribute is `#[late_init]` or `#[late_init(maybe)]`.
+fn parse_late_init(attr: &[TokenTree]) -> Result<Option<Container>, &'static str> {
+    let inner: Vec<TokenTree> = match &attr[1] {
+        TokenTree::Group(g) => g.stream().into_iter().collect(),
+        _ => return Ok(None),
+    };
+    if !inner.first().map_or(false, |tt| is_ident(tt, "late_init")) {
+        return Ok(None);
+    }
+    match &inner[1..] {
+        [] => Ok(Some(Container::Freeze)),
+        [TokenTree::Group(args)] if args.delimiter() == Delimiter::Parenthesis => {
+            let args: Vec<TokenTree> = args.stream().into_iter().collect();
+            match &args[..] {
+                [arg] if is_ident(arg, "maybe") => Ok(Some(Container::Maybe)),
+                [arg] if is_ident(arg, "freeze") => Ok(Some(Container::Freeze)),
+                _ => Err("expected `#[late_init]` or `#[late_init(maybe)]`"),
+            }
+        }
+        _ => Err("expected `#[late_init]` or `#[late_init(maybe)]`"),
+    }
+}
+
+fn parse_field(tokens: &[TokenTree]) -> Result<Field, &'static str> {
+    let (attr_list, rest) = take_attrs(tokens);
+    let (vis, rest) = take_vis(rest);
+
+    let mut attrs = String::new();
+    let mut late = None;
+    for attr in attr_list {
+        match parse_late_init(attr)? {
+            Some(container) => late = Some(container),
+            None => {
+                attrs.push_str(&to_string(attr));
+                attrs.push(' ');
+            }
+        }
+    }
+
+    match rest {
+        [TokenTree::Ident(name), colon, ty @ ..] if is_punct(colon, ':') && !ty.is_empty() => {
+            Ok(Field {
+                attrs,
+                vis: to_string(vis),
+                name: name.to_string(),
+                ty: to_string(ty),
+                late,
+            })
+        }
+        _ => Err("`#[late_init]` only supports structs with named fields"),
+    }
+}
+
+/// Convert generic parameter declarations into the parameters to use in
+/// an `impl` block (without defaults) and the matching type arguments.
+fn split_generics(decl: &[TokenTree]) -> (String, String) {
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    for param in split_commas(decl) {
+        // Strip defaults, which aren't allowed in `impl` blocks.
+        let param = match param.iter().position(|tt| is_punct(tt, '=')) {
+            Some(eq) => &param[..eq],
+            None => param,
+        };
+        params.push(to_string(param));
+        args.push(match param {
+            // A lifetime: `'a` or `'a: 'b`
+            [TokenTree::Punct(tick), name, ..] if tick.as_char() == '\'' => {
+                format!("'{}", name)
+            }
+            // A const parameter: `const N: usize`
+            [kw, name, ..] if is_ident(kw, "const") => name.to_string(),
+            // A type parameter: `T` or `T: Bound`
+            [name, ..] => name.to_string(),
+            [] => String::new(),
+        });
+    }
+    (params.join(", "), args.join(", "))
+}
+
+fn expand(item: TokenStream) -> Result<TokenStream, &'static str> {
+    let tokens: Vec<TokenTree> = item.into_iter().collect();
+    let (attrs, rest) = take_attrs(&tokens);
+    let (vis, rest) = take_vis(rest);
+
+    let (name, rest) = match rest {
+        [kw, TokenTree::Ident(name), rest @ ..] if is_ident(kw, "struct") => (name, rest),
+        _ => return Err("`#[late_init]` can only be used on a struct"),
+    };
+
+    // Generic parameters, if any.
+    let mut generics: &[TokenTree] = &[];
+    let mut rest = rest;
+    if rest.first().map_or(false, |tt| is_punct(tt, '<')) {
+        let mut depth = 0;
+        let mut end = None;
+        for (i, tt) in rest.iter().enumerate() {
+            if is_punct(tt, '<') {
+                depth += 1;
+            } else if is_punct(tt, '>') && !is_punct(&rest[i - 1], '-') {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            }
+        }
+        let end = end.ok_or("unbalanced generic parameters")?;
+        generics = &rest[1..end];
+        rest = &rest[end + 1..];
+    }
+
+    // An optional where clause, followed by the fields.
+    let (where_clause, body) = match rest.split_last() {
+        Some((TokenTree::Group(body), where_clause)) if body.delimiter() == Delimiter::Brace => {
+            (where_clause, body)
+        }
+        _ => return Err("`#[late_init]` only supports structs with named fields"),
+    };
+
+    let body: Vec<TokenTree> = body.stream().into_iter().collect();
+    let fields = split_commas(&body)
+        .into_iter()
+        .map(parse_field)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (impl_params, type_args) = split_generics(generics);
+    let where_clause = to_string(where_clause);
+
+    let mut field_defs = String::new();
+    let mut accessors = String::new();
+    for field in &fields {
+        let Field {
+            attrs,
+            vis,
+            name,
+            ty,
+            late,
+        } = field;
+        match late {
+            None => field_defs.push_str(&format!("{} {} {}: {},\n", attrs, vis, name, ty)),
+            Some(container) => {
+                let (container_ty, getter_ty, getter_body) = match container {
+                    Container::Freeze => ("FreezeBox", format!("&{}", ty), "&*self.{}"),
+                    Container::Maybe => ("MaybeBox", format!("Option<&{}>", ty), "self.{}.get()"),
+                };
+                field_defs.push_str(&format!(
+                    "{} {}: ::freezebox::{}<{}>,\n",
+                    attrs, name, container_ty, ty
+                ));
+                accessors.push_str(&format!(
+                    "#[doc = \"Get the `{name}` field.\"]\n\
+                     #[allow(dead_code)]\n\
+                     {vis} fn {name}(&self) -> {getter_ty} {{ {body} }}\n\
+                     #[doc = \"Initialize the `{name}` field.\"]\n\
+                     #[allow(dead_code)]\n\
+                     {vis} fn set_{name}(&self, val: {ty}) {{ self.{name}.lazy_init(val); }}\n",
+                    name = name,
+                    vis = vis,
+                    ty = ty,
+                    getter_ty = getter_ty,
+                    body = getter_body.replace("{}", name),
+                ));
+            }
+        }
+    }
+
+    let attrs: String = attrs.iter().map(|attr| to_string(attr) + " ").collect();
+    let output = format!(
+        "{attrs} {vis} struct {name}<{generics}> {where_clause} {{\n{field_defs}}}\n\
+         impl<{impl_params}> {name}<{type_args}> {where_clause} {{\n{accessors}}}\n",
+        attrs = attrs,
+        vis = to_string(vis),
+        name = name,
+        generics = to_string(generics),
+        impl_params = impl_params,
+        type_args = type_args,
+        where_clause = where_clause,
+        field_defs = field_defs,
+        accessors = accessors,
+    );
+    output
+        .parse()
+        .map_err(|_| "`#[late_init]` generated invalid code")
+}
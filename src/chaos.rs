@@ -0,0 +1,143 @@
+//! Fault injection for the `chaos` feature.
+//!
+//! With the `chaos` feature, containers randomly delay publishing their
+//! values, weak compare-exchange loops randomly fail and retry, and
+//! waiters randomly wake up before their condition is true.
+//! This widens race windows, so that downstream code that depends on
+//! timing it shouldn't (for example, assuming a value published on one
+//! thread is visible to another without synchronizing) fails more often
+//! under test.
+//!
+//! Without the feature, every function here is a no-op.
+
+#[cfg(feature = "chaos")]
+use core::cell::Cell;
+#[cfg(feature = "chaos")]
+use core::hash::{BuildHasher, Hash, Hasher};
+#[cfg(feature = "chaos")]
+use core::time::Duration;
+#[cfg(feature = "chaos")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "chaos")]
+use std::thread;
+
+#[cfg(feature = "chaos")]
+std::thread_local! {
+    static RNG: Cell<u64> = Cell::new(seed());
+}
+
+/// Create a per-thread random seed.
+#[cfg(feature = "chaos")]
+fn seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    thread::current().id().hash(&mut hasher);
+    // xorshift gets stuck at zero.
+    hasher.finish() | 1
+}
+
+/// Return a pseudo-random number.
+#[cfg(feature = "chaos")]
+fn random() -> u64 {
+    RNG.with(|rng| {
+        // xorshift64
+        let mut x = rng.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        rng.set(x);
+        x
+    })
+}
+
+/// Return true, one time in `n`.
+#[cfg(feature = "chaos")]
+fn one_in(n: u64) -> bool {
+    random() % n == 0
+}
+
+/// Maybe delay the current thread, just before a value is published.
+#[inline]
+pub(crate) fn delay() {
+    #[cfg(feature = "chaos")]
+    {
+        if one_in(4) {
+            thread::yield_now();
+        }
+        if one_in(16) {
+            thread::sleep(Duration::from_micros(random() % 100));
+        }
+    }
+}
+
+/// Decide whether a waiter should wake up spuriously.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn spurious_wakeup() -> bool {
+    #[cfg(feature = "chaos")]
+    {
+        one_in(4)
+    }
+    #[cfg(not(feature = "chaos"))]
+    {
+        false
+    }
+}
+
+/// Decide whether a `compare_exchange_weak` in a retry loop should fail
+/// spuriously.
+///
+/// A weak compare-exchange is allowed to fail even when the comparison
+/// succeeds, so callers must already handle this.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn fail_cas() -> bool {
+    #[cfg(feature = "chaos")]
+    {
+        one_in(4)
+    }
+    #[cfg(not(feature = "chaos"))]
+    {
+        false
+    }
+}
+
+#[cfg(all(test, feature = "chaos"))]
+mod tests {
+    use super::{one_in, random};
+    use crate::{CountdownLatch, FreezeBox};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::thread;
+
+    #[test]
+    fn random_test() {
+        let a = random();
+        let b = random();
+        assert_ne!(a, b);
+        assert!((0..1000).any(|_| one_in(2)));
+        assert!((0..1000).any(|_| !one_in(2)));
+    }
+
+    #[test]
+    fn soak_test() {
+        for _ in 0..20 {
+            let latch = Arc::new(CountdownLatch::new(1).with_spin_limit(0));
+            let x = Arc::new(FreezeBox::<u32>::default());
+            let threads: Vec<_> = (0..4)
+                .map(|_| {
+                    let latch = latch.clone();
+                    let x = x.clone();
+                    thread::spawn(move || {
+                        latch.wait();
+                        **x
+                    })
+                })
+                .collect();
+            x.lazy_init(7);
+            latch.count_down();
+            for t in threads {
+                assert_eq!(t.join().unwrap(), 7);
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! This is the CountdownLatch implementation.
+
+use crate::waiter::{Signal, Wait, Waiters};
+use crate::Error;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// `CountdownLatch` is a latch that opens after a fixed number of events.
+///
+/// A `CountdownLatch` is created with a count. Each call to `count_down`
+/// decrements the count, and once it reaches zero everyone waiting on the
+/// latch is woken up. This is useful for waiting until several independent
+/// subsystems have finished their initialization.
+///
+/// Both blocking ([`wait`]) and async ([`wait_async`]) waiting are
+/// supported.
+///
+/// This type requires the `std` feature.
+///
+/// [`wait`]: CountdownLatch::wait
+/// [`wait_async`]: CountdownLatch::wait_async
+///
+/// # Examples
+/// ```
+/// use freezebox::{CountdownLatch, FreezeBox};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct Subsystems {
+///     network: FreezeBox<String>,
+///     storage: FreezeBox<String>,
+/// }
+///
+/// let subsystems = Arc::new(Subsystems::default());
+/// let latch = Arc::new(CountdownLatch::new(2));
+///
+/// let (s, l) = (subsystems.clone(), latch.clone());
+/// std::thread::spawn(move || {
+///     s.network.lazy_init("eth0".to_string());
+///     l.count_down();
+/// });
+/// let (s, l) = (subsystems.clone(), latch.clone());
+/// std::thread::spawn(move || {
+///     s.storage.lazy_init("/dev/sda".to_string());
+///     l.count_down();
+/// });
+///
+/// latch.wait();
+/// assert_eq!(*subsystems.network, "eth0");
+/// assert_eq!(*subsystems.storage, "/dev/sda");
+/// ```
+pub struct CountdownLatch {
+    count: AtomicUsize,
+    waiters: Waiters,
+}
+
+impl CountdownLatch {
+    /// Create a new `CountdownLatch` with the given count.
+    ///
+    /// If `count` is zero, the latch starts out open.
+    pub fn new(count: usize) -> Self {
+        Self {
+            count: AtomicUsize::new(count),
+            waiters: Waiters::default(),
+        }
+    }
+
+    /// Set the number of times a blocking wait checks the latch before
+    /// putting the thread to sleep.
+    ///
+    /// Latches are usually opened soon after a waiter arrives, so a short spin
+    /// avoids the latency of parking and unparking the thread. A limit of
+    /// zero disables spinning. The default is 100.
+    pub fn with_spin_limit(mut self, spin_limit: u32) -> Self {
+        self.waiters.set_spin_limit(spin_limit);
+        self
+    }
+
+    /// Decrement the count, waking everyone waiting if it reaches zero.
+    ///
+    /// Calling `count_down` on an open latch has no effect.
+    pub fn count_down(&self) {
+        let mut count = self.count.load(Ordering::Acquire);
+        loop {
+            if count == 0 {
+                return;
+            }
+            if crate::chaos::fail_cas() {
+                count = self.count.load(Ordering::Acquire);
+                continue;
+            }
+            // `Release` ordering ensures that anything done before
+            // `count_down` is visible to threads that observe the latch
+            // as open.
+            match self.count.compare_exchange_weak(
+                count,
+                count - 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(current) => count = current,
+            }
+        }
+        if count == 1 {
+            self.waiters.notify_all();
+        }
+    }
+
+    /// Return the current count.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    /// Test whether the count has reached zero.
+    pub fn is_open(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Block the current thread until the count reaches zero.
+    pub fn wait(&self) {
+        self.waiters.wait(|| self.is_open());
+    }
+
+    /// Block the current thread until the count reaches zero, or until
+    /// `timeout` has elapsed.
+    ///
+    /// Returns [`Error::Timeout`] if the timeout expired first.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<(), Error> {
+        if self.waiters.wait_timeout(|| self.is_open(), timeout) {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Wait asynchronously until the count reaches zero.
+    pub fn wait_async(&self) -> Wait<'_> {
+        Wait::new(self)
+    }
+}
+
+impl Signal for CountdownLatch {
+    fn is_ready(&self) -> bool {
+        self.is_open()
+    }
+
+    fn waiters(&self) -> &Waiters {
+        &self.waiters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CountdownLatch;
+    use crate::waiter::block_on;
+    use crate::Error;
+    use alloc::sync::Arc;
+    use core::time::Duration;
+    use std::thread;
+
+    #[test]
+    fn countdown_test() {
+        let x = Arc::new(CountdownLatch::new(3));
+        assert_eq!(x.count(), 3);
+        assert!(!x.is_open());
+        x.count_down();
+        assert_eq!(x.count(), 2);
+        assert_eq!(
+            x.wait_timeout(Duration::from_millis(1)),
+            Err(Error::Timeout)
+        );
+
+        let x2 = x.clone();
+        let waiter = thread::spawn(move || x2.wait());
+        let x2 = x.clone();
+        let async_waiter = thread::spawn(move || block_on(x2.wait_async()));
+
+        let handles: alloc::vec::Vec<_> = (0..2)
+            .map(|_| {
+                let x = x.clone();
+                thread::spawn(move || x.count_down())
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        waiter.join().unwrap();
+        async_waiter.join().unwrap();
+        assert!(x.is_open());
+
+        // Counting down an open latch does nothing.
+        x.count_down();
+        assert_eq!(x.count(), 0);
+    }
+
+    #[test]
+    fn zero_test() {
+        let x = CountdownLatch::new(0);
+        assert!(x.is_open());
+        x.wait();
+        block_on(x.wait_async());
+    }
+}
@@ -0,0 +1,120 @@
+//! The crate's error type.
+
+use core::fmt;
+
+/// Errors returned by fallible operations in this crate.
+///
+/// `AlreadyInitialized` is returned by the `set` methods of
+/// [`OnceNonZeroUsize`], [`OnceBool`] and [`OnceRef`]. `Timeout` is
+/// returned by the `wait_timeout` methods of `CountdownLatch`, `OnceEvent`
+/// and `NamedRegistry`.
+///
+/// The lazy-init containers, like [`FreezeBox`], don't use this type.
+/// Their fallible methods return the rejected value instead, so that the
+/// caller can recover it.
+///
+/// [`OnceNonZeroUsize`]: crate::OnceNonZeroUsize
+/// [`OnceBool`]: crate::OnceBool
+/// [`OnceRef`]: crate::OnceRef
+/// [`FreezeBox`]: crate::FreezeBox
+///
+/// New variants may be added in the future, so matches on this type must
+/// include a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The container was already initialized.
+    AlreadyInitialized,
+    /// A timeout expired before the operation could complete.
+    Timeout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Error::AlreadyInitialized => "already initialized",
+            Error::Timeout => "timed out",
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// The error returned when a value is rejected by a validation check.
+///
+/// This is returned by methods like [`FreezeBox::lazy_init_validated`]. It
+/// holds the error returned by the check, and the rejected value, so that
+/// the caller can recover it.
+///
+/// [`FreezeBox::lazy_init_validated`]: crate::FreezeBox::lazy_init_validated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InitError<E, T> {
+    error: E,
+    value: T,
+}
+
+impl<E, T> InitError<E, T> {
+    pub(crate) fn new(error: E, value: T) -> Self {
+        Self { error, value }
+    }
+
+    /// Return the error returned by the check.
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Return the rejected value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Consume the `InitError` and return the rejected value.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Consume the `InitError` and return the error and the rejected value.
+    pub fn into_parts(self) -> (E, T) {
+        (self.error, self.value)
+    }
+}
+
+impl<E: fmt::Display, T> fmt::Display for InitError<E, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value rejected: {}", self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static, T: fmt::Debug> std::error::Error for InitError<E, T> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, InitError};
+    use alloc::string::ToString;
+
+    #[test]
+    fn display_test() {
+        assert_eq!(Error::AlreadyInitialized.to_string(), "already initialized");
+        assert_eq!(Error::Timeout.to_string(), "timed out");
+        assert_eq!(
+            InitError::new("too big", 5).to_string(),
+            "value rejected: too big"
+        );
+    }
+
+    #[test]
+    fn init_error_test() {
+        let e = InitError::new("too big", 5);
+        assert_eq!(*e.error(), "too big");
+        assert_eq!(*e.value(), 5);
+        assert_eq!(e.clone().into_value(), 5);
+        assert_eq!(e.into_parts(), ("too big", 5));
+    }
+}
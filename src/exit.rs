@@ -0,0 +1,152 @@
+//! Dropping the contents of static containers at process exit.
+
+use alloc::boxed::Box;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// A container whose contents can be dropped at exit.
+pub(crate) trait ExitCleanup: Sync {
+    /// Reset the container, dropping its contents.
+    ///
+    /// # Safety
+    ///
+    /// No references to the contents may exist, and no other thread may
+    /// be accessing the container.
+    unsafe fn exit_cleanup(&self);
+}
+
+/// A registered container, stored in a singly-linked list.
+struct Node {
+    target: &'static dyn ExitCleanup,
+    next: *mut Node,
+}
+
+/// The list of registered containers, most recent first.
+static REGISTERED: AtomicPtr<Node> = AtomicPtr::new(ptr::null_mut());
+
+/// Add a container to the list of containers reset at exit.
+pub(crate) fn register(target: &'static dyn ExitCleanup) {
+    #[cfg(unix)]
+    {
+        use std::sync::Once;
+
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            // SAFETY: `exit_handler` is a valid `extern "C"` function that
+            // stays alive for the life of the process.
+            let result = unsafe { atexit(exit_handler) };
+            assert_eq!(result, 0, "atexit failed");
+        });
+    }
+
+    let node = Box::into_raw(Box::new(Node {
+        target,
+        next: ptr::null_mut(),
+    }));
+    let mut head = REGISTERED.load(Ordering::Acquire);
+    loop {
+        // SAFETY: `node` is not yet published, so we are its unique owner.
+        unsafe { (*node).next = head };
+        match REGISTERED.compare_exchange_weak(head, node, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => break,
+            Err(new_head) => head = new_head,
+        }
+    }
+}
+
+/// Drop the contents of every container registered with
+/// `register_exit_cleanup`, in reverse order of registration.
+///
+/// Each registered container is reset once; containers registered later
+/// will be reset by a later call. On unix platforms, this is called
+/// automatically when the process exits normally. Call it explicitly on
+/// other platforms, or to control exactly when cleanup happens.
+///
+/// This function requires the `exit-cleanup` feature.
+///
+/// # Safety
+///
+/// No references to the contents of the registered containers may exist,
+/// and no other thread may be accessing them.
+///
+/// # Examples
+/// ```
+/// use freezebox::{run_exit_cleanups, FreezeBox};
+/// use std::io::{BufWriter, Write};
+///
+/// static LOG: FreezeBox<std::sync::Mutex<BufWriter<Vec<u8>>>> = FreezeBox::const_default();
+///
+/// LOG.lazy_init(std::sync::Mutex::new(BufWriter::new(Vec::new())));
+/// // SAFETY: LOG is not used after cleanup.
+/// unsafe { LOG.register_exit_cleanup() };
+/// writeln!(LOG.lock().unwrap(), "hello").unwrap();
+///
+/// // At shutdown, the BufWriter is dropped and flushed.
+/// unsafe { run_exit_cleanups() };
+/// assert!(!LOG.is_initialized());
+/// ```
+pub unsafe fn run_exit_cleanups() {
+    // Take the whole list, so that each container is reset only once.
+    // Destructors may register more containers, so repeat until the list
+    // stays empty.
+    loop {
+        let mut cur = REGISTERED.swap(ptr::null_mut(), Ordering::AcqRel);
+        if cur.is_null() {
+            break;
+        }
+        while !cur.is_null() {
+            // SAFETY: every node was created by `Box::into_raw`, and we
+            // removed this one from the list, so we are its unique owner.
+            let node = Box::from_raw(cur);
+            node.target.exit_cleanup();
+            cur = node.next;
+        }
+    }
+}
+
+#[cfg(unix)]
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+}
+
+#[cfg(unix)]
+extern "C" fn exit_handler() {
+    // SAFETY: the caller of `register_exit_cleanup` promised that the
+    // contents aren't used once the process starts exiting.
+    unsafe { run_exit_cleanups() };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run_exit_cleanups;
+    use crate::{FreezeBox, MaybeBox};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// The digits of each `Noisy` value, in the order they were dropped.
+    static ORDER: AtomicUsize = AtomicUsize::new(0);
+
+    struct Noisy(usize);
+
+    impl Drop for Noisy {
+        fn drop(&mut self) {
+            let order = ORDER.load(Ordering::Relaxed);
+            ORDER.store(order * 10 + self.0, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn exit_cleanup_test() {
+        static X: FreezeBox<Noisy> = FreezeBox::const_default();
+        static Y: MaybeBox<Noisy> = MaybeBox::const_default();
+        X.lazy_init(Noisy(1));
+        Y.lazy_init(Noisy(2));
+        unsafe {
+            X.register_exit_cleanup();
+            Y.register_exit_cleanup();
+            run_exit_cleanups();
+        }
+        assert!(!X.is_initialized());
+        assert!(!Y.is_initialized());
+        assert_eq!(ORDER.load(Ordering::Relaxed), 21);
+    }
+}
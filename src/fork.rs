@@ -0,0 +1,106 @@
+//! Clearing containers in the child process after `fork()`.
+
+use alloc::boxed::Box;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::os::raw::c_int;
+use std::sync::Once;
+
+extern "C" {
+    fn pthread_atfork(
+        prepare: Option<unsafe extern "C" fn()>,
+        parent: Option<unsafe extern "C" fn()>,
+        child: Option<unsafe extern "C" fn()>,
+    ) -> c_int;
+}
+
+/// A container that can be cleared in a forked child.
+pub(crate) trait ForkClear: Sync {
+    /// Return the container to the uninitialized state, leaking its
+    /// contents.
+    fn fork_clear(&self);
+}
+
+/// A registered container, stored in a singly-linked list.
+struct Node {
+    target: &'static dyn ForkClear,
+    next: *mut Node,
+}
+
+/// The list of registered containers. Nodes are never freed.
+static REGISTERED: AtomicPtr<Node> = AtomicPtr::new(ptr::null_mut());
+
+/// Add a container to the list of containers cleared after `fork()`.
+pub(crate) fn register(target: &'static dyn ForkClear) {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        // SAFETY: `child_handler` is a valid `extern "C"` function that
+        // stays alive for the life of the process.
+        let result = unsafe { pthread_atfork(None, None, Some(child_handler)) };
+        assert_eq!(result, 0, "pthread_atfork failed");
+    });
+
+    let node = Box::into_raw(Box::new(Node {
+        target,
+        next: ptr::null_mut(),
+    }));
+    let mut head = REGISTERED.load(Ordering::Acquire);
+    loop {
+        // SAFETY: `node` is not yet published, so we are its unique owner.
+        unsafe { (*node).next = head };
+        match REGISTERED.compare_exchange_weak(head, node, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => break,
+            Err(new_head) => head = new_head,
+        }
+    }
+}
+
+/// Clear every registered container.
+///
+/// This runs in the child process after `fork()`, where only
+/// async-signal-safe operations are allowed. It only performs atomic
+/// loads and stores, and never allocates, locks, or runs destructors.
+pub(crate) fn clear_registered() {
+    let mut cur = REGISTERED.load(Ordering::Acquire);
+    // SAFETY: every node was created by `Box::into_raw` and is never freed.
+    while let Some(node) = unsafe { cur.as_ref() } {
+        node.target.fork_clear();
+        cur = node.next;
+    }
+}
+
+unsafe extern "C" fn child_handler() {
+    clear_registered();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clear_registered;
+    use crate::{FreezeBox, MaybeBox};
+    use alloc::string::String;
+    use alloc::string::ToString;
+
+    #[test]
+    fn fork_clear_test() {
+        static X: FreezeBox<String> = FreezeBox::const_default();
+        static Y: MaybeBox<String> = MaybeBox::const_default();
+        X.clear_on_fork();
+        Y.clear_on_fork();
+        X.lazy_init("parent".to_string());
+        Y.lazy_init("parent".to_string());
+        let old_x: &str = &X;
+
+        // Emulate the child handler running after fork().
+        clear_registered();
+        assert!(!X.is_initialized());
+        assert!(!Y.is_initialized());
+
+        // Existing references remain valid.
+        assert_eq!(old_x, "parent");
+
+        X.lazy_init("child".to_string());
+        Y.lazy_init("child".to_string());
+        assert_eq!(*X, "child");
+        assert_eq!(Y.get().unwrap(), "child");
+    }
+}
@@ -0,0 +1,270 @@
+//! This is the FreezeArc implementation.
+
+use alloc::sync::{Arc, Weak};
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::Deref;
+use core::ptr::{self, null_mut};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// `FreezeArc` is a deref-able lazy-initialized `Arc`.
+///
+/// A `FreezeArc<T>` behaves like a `FreezeBox<Arc<T>>`, but it stores the
+/// `Arc`'s pointer directly, so a deref is a single pointer chase instead
+/// of two. It can also hand out clones of the `Arc` with [`get_arc`].
+///
+/// (There is no equivalent for `FreezeBox<Box<T>>`, because a
+/// `FreezeBox<T>` already stores its value in a single heap allocation.)
+///
+/// # Panics
+///
+/// Attempting to `lazy_init` more than once, or deref while uninitialized
+/// will cause a panic.
+///
+/// [`get_arc`]: FreezeArc::get_arc
+///
+/// # Examples
+/// ```
+/// use freezebox::FreezeArc;
+/// use std::sync::Arc;
+///
+/// let config = Arc::new("config".to_string());
+/// let x = FreezeArc::<String>::default();
+/// x.lazy_init(config.clone());
+///
+/// assert_eq!(x.len(), 6);
+/// assert!(Arc::ptr_eq(&x.get_arc().unwrap(), &config));
+/// ```
+pub struct FreezeArc<T> {
+    /// A pointer created by `Arc::into_raw`, or null.
+    inner: AtomicPtr<T>,
+    phantom: PhantomData<Arc<T>>,
+}
+
+impl<T> FreezeArc<T> {
+    /// Create a new `FreezeArc` with optional initialization.
+    ///
+    /// To always create an uninitialized `FreezeArc`, use
+    /// `FreezeArc::default()`.
+    pub fn new(val: Option<Arc<T>>) -> Self {
+        let fa = Self::default();
+        if let Some(v) = val {
+            fa.lazy_init(v);
+        }
+        fa
+    }
+
+    /// Create a new `FreezeArc` in `const` context
+    ///
+    /// This is the same as `FreezeArc::default` except that it works in
+    /// const context, which is desirable for global `static` singleton objects.
+    pub const fn const_default() -> Self {
+        Self {
+            inner: AtomicPtr::new(null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Initialize a `FreezeArc`.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the `FreezeArc` is already initialized.
+    /// If it panics, the input `Arc` will be dropped.
+    pub fn lazy_init(&self, val: Arc<T>) {
+        let ptr = Arc::into_raw(val) as *mut T;
+
+        // The atomic ordering here is the same as `FreezeBox::lazy_init`.
+        crate::chaos::delay();
+        if self
+            .inner
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // SAFETY: `ptr` was just created above using `Arc::into_raw`,
+            // and because compare_exchange failed, we still own that
+            // reference count.
+            let _val = unsafe { Arc::from_raw(ptr) };
+
+            panic_with_type!("lazy_init on already-initialized FreezeArc", T);
+        }
+    }
+
+    /// Get a clone of the inner `Arc`, if the `FreezeArc` is initialized.
+    pub fn get_arc(&self) -> Option<Arc<T>> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: `ptr` was created by `Arc::into_raw`, and the reference
+        // count it represents is owned by `self`, so it's still alive. We
+        // must not drop that count, so we only clone it.
+        let arc = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        Some(Arc::clone(&arc))
+    }
+
+    /// Get a `Weak` reference to the inner value, if the `FreezeArc` is
+    /// initialized.
+    pub fn downgrade(&self) -> Option<Weak<T>> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: see `get_arc`.
+        let arc = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        Some(Arc::downgrade(&arc))
+    }
+
+    /// Test whether a `FreezeArc` is initialized.
+    pub fn is_initialized(&self) -> bool {
+        let ptr = self.inner.load(Ordering::Acquire);
+        !ptr.is_null()
+    }
+
+    /// Consume the `FreezeArc` and return its contents.
+    pub fn into_inner(self) -> Option<Arc<T>> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // Prevent Drop::drop() from being called on the FreezeArc
+        // because we are transferring ownership elsewhere.
+        mem::forget(self);
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: `ptr` was created by `Arc::into_raw`, and we are
+        // consuming the reference count owned by `self`.
+        Some(unsafe { Arc::from_raw(ptr) })
+    }
+}
+
+impl<T> Deref for FreezeArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        let inner = self.inner.load(Ordering::Acquire);
+
+        // SAFETY: the pointer is either null, or was created by
+        // `Arc::into_raw`. Because `self` owns a reference count, the
+        // value lives at least as long as `self`.
+        let inner_ref = unsafe { inner.as_ref() };
+        inner_ref
+            .unwrap_or_else(|| panic_with_type!("attempted to deref uninitialized FreezeArc", T))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FreezeArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: see `Deref`.
+        match unsafe { ptr.as_ref() } {
+            Some(val) => f.debug_tuple("FreezeArc").field(val).finish(),
+            None => f.write_str("FreezeArc(<uninitialized>)"),
+        }
+    }
+}
+
+impl<T> Default for FreezeArc<T> {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+impl<T> Drop for FreezeArc<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+
+        if !inner.is_null() {
+            // SAFETY: `inner` was created by `Arc::into_raw`, and we own
+            // its reference count.
+            let _owned = unsafe { Arc::<T>::from_raw(*inner) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `FreezeArc` owns an `Arc<T>`, so it's safe to send or share
+// whenever `Arc<T>` is.
+unsafe impl<T: Send + Sync> Send for FreezeArc<T> {}
+unsafe impl<T: Send + Sync> Sync for FreezeArc<T> {}
+
+/// Must fail to compile because FreezeArc<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::FreezeArc;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = FreezeArc::<Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::FreezeArc;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn freezearc_test() {
+        let x = Arc::new("hello".to_string());
+        let y = FreezeArc::<String>::default();
+        assert!(!y.is_initialized());
+        assert!(y.get_arc().is_none());
+        y.lazy_init(x.clone());
+        assert!(y.is_initialized());
+        assert_eq!(*y, "hello");
+        assert_eq!(&y[2..], "llo");
+
+        let z = y.get_arc().unwrap();
+        assert!(Arc::ptr_eq(&x, &z));
+        assert_eq!(Arc::strong_count(&x), 3);
+        drop(z);
+        drop(y);
+        assert_eq!(Arc::strong_count(&x), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_deref() {
+        let x = FreezeArc::<String>::default();
+        let _y = x.len();
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_double_init() {
+        let x = FreezeArc::<String>::new(Some(Arc::new("first".to_string())));
+        x.lazy_init(Arc::new("second".to_string()));
+    }
+
+    #[test]
+    fn downgrade_test() {
+        let x = FreezeArc::<String>::default();
+        assert!(x.downgrade().is_none());
+        x.lazy_init(Arc::new("hello".to_string()));
+        let weak = x.downgrade().unwrap();
+        assert_eq!(*weak.upgrade().unwrap(), "hello");
+        drop(x);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn consume_test() {
+        let x = Arc::new("hello".to_string());
+        let y = FreezeArc::new(Some(x.clone()));
+        assert!(Arc::ptr_eq(&y.into_inner().unwrap(), &x));
+        assert_eq!(Arc::strong_count(&x), 1);
+        assert!(FreezeArc::<String>::default().into_inner().is_none());
+    }
+
+    #[test]
+    fn const_test() {
+        static X: FreezeArc<String> = FreezeArc::const_default();
+        X.lazy_init(Arc::new("hello".to_string()));
+        assert_eq!(*X, "hello");
+        assert_eq!(format!("{:?}", X), "FreezeArc(\"hello\")");
+    }
+}
@@ -1,11 +1,16 @@
 //! This is the FreezeBox implementation.
 
 extern crate alloc;
+use crate::initinfo::InfoSlot;
+#[cfg(feature = "init-info")]
+use crate::initinfo::InitInfo;
+use crate::{InitError, MaybeBox};
 use alloc::boxed::Box;
-use core::any::type_name;
+use core::fmt;
 use core::marker::PhantomData;
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
 use core::ptr::null_mut;
+use core::str::FromStr;
 use core::sync::atomic::{AtomicPtr, Ordering};
 use core::{mem, ptr};
 
@@ -61,9 +66,12 @@ use core::{mem, ptr};
 /// resources.name.lazy_init("Hello!".to_string());
 /// func();
 /// ```
-
+// `FreezeBox` and `MaybeBox` must have identical layouts, so that
+// `as_maybe` and `as_freeze` can convert references between them.
+#[repr(C)]
 pub struct FreezeBox<T> {
     inner: AtomicPtr<T>,
+    info: InfoSlot,
     phantom: PhantomData<T>,
 }
 
@@ -103,20 +111,39 @@ impl<T> FreezeBox<T> {
     pub const fn const_default() -> Self {
         Self {
             inner: AtomicPtr::new(null_mut()),
+            info: InfoSlot::new(),
             phantom: PhantomData,
         }
     }
 
     /// Initialize a `FreezeBox`.
     ///
-    /// The new value will be stored on the heap.
+    /// The new value will be stored on the heap. Returns a reference to
+    /// the stored value.
     ///
     /// # Panics
     ///
     /// `lazy_init` will panic if the `FreezeBox` is already initialized.
     /// If it panics, the input value will be dropped.
-    pub fn lazy_init(&self, val: T) {
-        let ptr = Box::into_raw(Box::new(val));
+    pub fn lazy_init(&self, val: T) -> &T {
+        self.lazy_init_box(Box::new(val))
+    }
+
+    /// Initialize a `FreezeBox` with a value that is already on the heap.
+    ///
+    /// This is the same as [`lazy_init`][FreezeBox::lazy_init], except that
+    /// the existing allocation is installed directly, instead of moving
+    /// the value into a new one.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_box` will panic if the `FreezeBox` is already initialized.
+    /// If it panics, the input value will be dropped.
+    pub fn lazy_init_box(&self, val: Box<T>) -> &T {
+        let ptr = Box::into_raw(val);
+        // Report the allocation once the container owns it, to balance the
+        // free reported when the container (or a failed init) drops it.
+        crate::observer::observe_alloc::<T>();
 
         // Attempt to atomically swap from nullptr to `ptr`.
         //
@@ -136,6 +163,7 @@ impl<T> FreezeBox<T> {
         // `AcqRel`.
         //
         // If this succeeds, the FreezeBox is now initialized.
+        crate::chaos::delay();
         if self
             .inner
             .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
@@ -153,12 +181,241 @@ impl<T> FreezeBox<T> {
             // ownership here and drop the result.
 
             let _val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+
+            panic_with_type!("lazy_init on already-initialized FreezeBox", T);
+        }
+        self.info.record();
+        // SAFETY: we just published `ptr`, and the value can't be removed
+        // while `self` is shared.
+        unsafe { &*ptr }
+    }
+
+    /// Initialize a `FreezeBox`, if it is not already initialized.
+    ///
+    /// This is the same as [`lazy_init`][FreezeBox::lazy_init], except that if
+    /// the `FreezeBox` is already initialized, `val` is returned in the `Err`
+    /// variant instead of panicking. This is useful when several
+    /// subsystems may try to install the same singleton.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// static LOGGER: FreezeBox<String> = FreezeBox::const_default();
+    /// assert_eq!(LOGGER.try_lazy_init("first".to_string()).unwrap(), "first");
+    /// assert_eq!(LOGGER.try_lazy_init("second".to_string()), Err("second".to_string()));
+    /// assert_eq!(*LOGGER, "first");
+    /// ```
+    pub fn try_lazy_init(&self, val: T) -> Result<&T, T> {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+
+        // The atomic ordering here is the same as `lazy_init`.
+        crate::chaos::delay();
+        if self
+            .inner
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // SAFETY: `ptr` was just created above using `Box::into_raw`,
+            // and because compare_exchange failed, we are still its unique
+            // owner.
+            let val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+            return Err(*val);
+        }
+        self.info.record();
+        // SAFETY: we just published `ptr`, and the value can't be removed
+        // while `self` is shared.
+        Ok(unsafe { &*ptr })
+    }
+
+    /// Initialize the `FreezeBox`, unless another value got there first.
+    ///
+    /// Returns a reference to whichever value was stored. If the `FreezeBox`
+    /// was already initialized, `val` is dropped (without panicking).
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let x = FreezeBox::<u32>::default();
+    /// assert_eq!(*x.race_init(1), 1);
+    /// assert_eq!(*x.race_init(2), 1);
+    /// ```
+    pub fn race_init(&self, val: T) -> &T {
+        match self.try_lazy_init(val) {
+            Ok(val) => val,
+            // Another thread won the race, so drop our value.
+            Err(_) => Self::get(self).unwrap(),
+        }
+    }
+
+    /// Get a reference to the data in the `FreezeBox`, initializing it with
+    /// `f` if necessary.
+    ///
+    /// If several threads race to initialize the `FreezeBox`, each may run its
+    /// own `f`, but only one value is stored. The other values are dropped
+    /// (without panicking), and every caller gets a reference to the
+    /// stored value.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let cache = FreezeBox::<Vec<u32>>::default();
+    /// assert_eq!(cache.get_or_init(|| vec![1, 2, 3]).len(), 3);
+    /// // Already initialized, so the closure doesn't run.
+    /// assert_eq!(cache.get_or_init(|| unreachable!()).len(), 3);
+    /// ```
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        match Self::get(self) {
+            Some(val) => val,
+            None => self.race_init(f()),
+        }
+    }
+
+    /// Get a reference to the data in the `FreezeBox`, initializing it with
+    /// the result of `f` if necessary.
+    ///
+    /// If `f` returns an error, the `FreezeBox` is left uninitialized and the
+    /// error is returned, so the next call runs its initializer again. To
+    /// remember the error instead, use
+    /// [`get_or_try_init_cached`][Self::get_or_try_init_cached]. Races
+    /// between initializers are handled as in
+    /// [`get_or_init`][Self::get_or_init].
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let port = FreezeBox::<u16>::default();
+    /// assert!(port.get_or_try_init(|| "http".parse::<u16>()).is_err());
+    /// assert!(!port.is_initialized());
+    /// assert_eq!(port.get_or_try_init(|| "8080".parse::<u16>()), Ok(&8080));
+    /// ```
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(val) = Self::get(self) {
+            return Ok(val);
+        }
+        Ok(self.race_init(f()?))
+    }
 
-            panic!(
-                "lazy_init on already-initialized FreezeBox<{}>",
-                type_name::<T>()
-            );
+    /// Get a reference to the data in the `FreezeBox`, initializing it with
+    /// the result of `f` if necessary, and caching any error in `errors`.
+    ///
+    /// Unlike [`get_or_try_init`][Self::get_or_try_init], a failed
+    /// initialization is remembered: once `errors` holds an error, later
+    /// calls return it without running `f`. If several threads fail at once,
+    /// only the first error is stored, and every caller gets a reference to
+    /// it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::{FreezeBox, MaybeBox};
+    /// let port = FreezeBox::<u16>::default();
+    /// let error = MaybeBox::default();
+    /// assert!(port.get_or_try_init_cached(&error, || "http".parse::<u16>()).is_err());
+    /// // The error is cached, so the closure doesn't run.
+    /// assert!(port.get_or_try_init_cached(&error, || unreachable!()).is_err());
+    /// assert!(!port.is_initialized());
+    /// ```
+    pub fn get_or_try_init_cached<'a, F, E>(
+        &'a self,
+        errors: &'a MaybeBox<E>,
+        f: F,
+    ) -> Result<&'a T, &'a E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(val) = Self::get(self) {
+            return Ok(val);
         }
+        if let Some(err) = errors.get() {
+            return Err(err);
+        }
+        match f() {
+            Ok(val) => Ok(self.race_init(val)),
+            Err(err) => Err(errors.race_init(err)),
+        }
+    }
+
+    /// Try to get a reference to the data in the `FreezeBox`.
+    ///
+    /// If the `FreezeBox` is initialized, this will return `Some(&T)`;
+    /// otherwise it will return None. Unlike `Deref`, this never panics,
+    /// which is useful in shutdown paths and diagnostics.
+    ///
+    /// This is an associated function, so that it doesn't hide a `get`
+    /// method on `T`, like `Vec::get`. Call it as `FreezeBox::get(&x)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let x = FreezeBox::<Vec<u32>>::default();
+    /// assert_eq!(FreezeBox::get(&x), None);
+    /// x.lazy_init(vec![1, 2]);
+    /// assert_eq!(FreezeBox::get(&x).unwrap().len(), 2);
+    /// // `x.get` is still `Vec::get`.
+    /// assert_eq!(x.get(1), Some(&2));
+    /// ```
+    pub fn get(this: &Self) -> Option<&T> {
+        let ptr = this.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or points to a value that
+        // lives as long as `this`; see `Deref`.
+        unsafe { ptr.as_ref() }
+    }
+
+    /// Get a reference to the data in the `FreezeBox`, or to `default` if
+    /// it is uninitialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// static DEFAULT_HOST: String = String::new();
+    /// let host = FreezeBox::<String>::default();
+    /// assert_eq!(host.deref_or(&DEFAULT_HOST), "");
+    /// host.lazy_init("example.com".to_string());
+    /// assert_eq!(host.deref_or(&DEFAULT_HOST), "example.com");
+    /// ```
+    pub fn deref_or<'a>(&'a self, default: &'a T) -> &'a T {
+        Self::get(self).unwrap_or(default)
+    }
+
+    /// Initialize a `FreezeBox` without checking whether it is already initialized.
+    ///
+    /// This performs a plain atomic store rather than a compare-exchange,
+    /// which is slightly cheaper when initializing many containers during
+    /// a single-threaded startup phase.
+    ///
+    /// # Safety
+    ///
+    /// The `FreezeBox` must be uninitialized, and no other thread may be
+    /// initializing it at the same time.
+    pub unsafe fn init_unchecked(&self, val: T) {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+        debug_assert!(self.inner.load(Ordering::Relaxed).is_null());
+        self.inner.store(ptr, Ordering::Release);
+        self.info.record();
+    }
+
+    /// Call a function with a reference to the data in the `FreezeBox`.
+    ///
+    /// This is equivalent to `f(&*self)`, but keeps the borrow scoped to
+    /// the closure.
+    ///
+    /// # Panics
+    ///
+    /// `with` will panic if the `FreezeBox` is uninitialized.
+    pub fn with<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(self)
     }
 
     /// Test whether a FreezeBox is initialized.
@@ -167,23 +424,302 @@ impl<T> FreezeBox<T> {
         !ptr.is_null()
     }
 
+    /// View this `FreezeBox` as a `MaybeBox`.
+    ///
+    /// This allows exposing the non-panicking `MaybeBox` interface for a
+    /// field that is stored as a `FreezeBox`, without copying or consuming
+    /// it. Both views share the same contents; initializing either one
+    /// initializes both.
+    pub fn as_maybe(&self) -> &MaybeBox<T> {
+        // SAFETY: `FreezeBox` and `MaybeBox` have identical layouts, and
+        // every state of one is a valid state of the other.
+        unsafe { &*(self as *const Self as *const MaybeBox<T>) }
+    }
+
+    /// Get details about the initialization of this `FreezeBox`.
+    ///
+    /// Returns the thread that called `lazy_init`, and when. This is
+    /// useful for debugging initialization-order bugs. The info may be
+    /// missing for a short time after another thread initializes the
+    /// `FreezeBox`.
+    ///
+    /// This method requires the `init-info` feature.
+    #[cfg(feature = "init-info")]
+    pub fn init_info(&self) -> Option<&InitInfo> {
+        self.info.get()
+    }
+
+    /// Assert that the `FreezeBox` is initialized.
+    ///
+    /// This is intended to be called at the end of a startup phase, so
+    /// that a missing initialization is reported where it can be
+    /// explained, rather than at some later use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `FreezeBox` is uninitialized. The panic message starts
+    /// with `context`, unless the `static-panic-messages` feature is
+    /// enabled.
+    ///
+    /// # Examples
+    /// ```should_panic
+    /// # use freezebox::FreezeBox;
+    /// let db_url = FreezeBox::<String>::default();
+    /// db_url.assert_initialized("startup incomplete: database URL not loaded");
+    /// ```
+    #[track_caller]
+    pub fn assert_initialized(&self, context: &str) {
+        if !self.is_initialized() {
+            panic_with_context!(context, "uninitialized FreezeBox", T);
+        }
+    }
+
+    /// Get a reference to the data in the `FreezeBox`, panicking with a custom
+    /// message if it is uninitialized.
+    ///
+    /// This is like `Option::expect`. The message helps to tell apart
+    /// several containers holding the same type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `FreezeBox` is uninitialized. The panic message starts
+    /// with `msg`, unless the `static-panic-messages` feature is enabled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let db_url = FreezeBox::<String>::default();
+    /// db_url.lazy_init("postgres://localhost".to_string());
+    /// assert_eq!(db_url.deref_expect("database URL not loaded"), "postgres://localhost");
+    /// ```
+    #[track_caller]
+    pub fn deref_expect(&self, msg: &str) -> &T {
+        match Self::get(self) {
+            Some(val) => val,
+            None => panic_with_context!(msg, "uninitialized FreezeBox", T),
+        }
+    }
+
+    /// Get a mutable reference to the data in the `FreezeBox`.
+    ///
+    /// Returns `None` if the `FreezeBox` is uninitialized. No atomic operations
+    /// are needed, because the mutable borrow guarantees exclusive access.
+    ///
+    /// Like [`get`][FreezeBox::get], this is an associated function, so
+    /// that it doesn't hide `get_mut` methods on `T`, like `Vec::get_mut`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let mut x = FreezeBox::new(Some(vec![1, 2]));
+    /// FreezeBox::get_mut(&mut x).unwrap().push(3);
+    /// *x.get_mut(0).unwrap() = 0;
+    /// assert_eq!(x.into_inner(), Some(vec![0, 2, 3]));
+    /// ```
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        let ptr = *this.inner.get_mut();
+        // SAFETY: the pointer is either null, or was created by
+        // `Box::into_raw` and is owned by `this`. We have exclusive access,
+        // so no other references to the value exist.
+        unsafe { ptr.as_mut() }
+    }
+
+    /// Take the value out of the `FreezeBox`, leaving it uninitialized.
+    ///
+    /// Returns `None` if the `FreezeBox` was already uninitialized. The
+    /// `FreezeBox` can be initialized again afterwards, which is useful when
+    /// recycling a container that isn't shared.
+    ///
+    /// This is an associated function, so that it doesn't hide a method
+    /// of the same name on `T`. Call it as `FreezeBox::take(&mut x)`.
+    pub fn take(this: &mut Self) -> Option<T> {
+        let ptr = mem::replace(this.inner.get_mut(), ptr::null_mut());
+        // SAFETY: we have exclusive access, so no references to the info
+        // exist.
+        unsafe { this.info.reset() };
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: `ptr` was created by `Box::into_raw` in `lazy_init`, and
+        // we just removed it from the container.
+        let owned = unsafe { Box::from_raw(ptr) };
+        crate::observer::observe_free::<T>();
+        Some(*owned)
+    }
+
+    /// Replace the value in the `FreezeBox`, returning the old value.
+    ///
+    /// Returns `None` if the `FreezeBox` was uninitialized. Because this
+    /// requires exclusive access, it doesn't conflict with the write-once
+    /// guarantee that shared references rely on.
+    ///
+    /// This is an associated function, so that it doesn't hide a method
+    /// of the same name on `T`. Call it as `FreezeBox::replace(&mut x, val)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let mut level = FreezeBox::<u8>::default();
+    /// assert_eq!(FreezeBox::replace(&mut level, 1), None);
+    /// assert_eq!(FreezeBox::replace(&mut level, 2), Some(1));
+    /// assert_eq!(*level, 2);
+    /// ```
+    pub fn replace(this: &mut Self, val: T) -> Option<T> {
+        let old = Self::take(this);
+        this.lazy_init(val);
+        old
+    }
+
     /// Consume the FreezeBox and return its contents.
     pub fn into_inner(self) -> Option<T> {
-        let ptr = self.inner.load(Ordering::Acquire);
+        let tmp_box = Self::into_box(self)?;
+        crate::observer::observe_free::<T>();
+        Some(*tmp_box)
+    }
+
+    /// Consume the `FreezeBox` and return its contents, without moving them
+    /// out of their heap allocation.
+    ///
+    /// This avoids copying large values. The allocation is handed to the
+    /// caller rather than freed, so it isn't reported as a free to the
+    /// alloc observer.
+    ///
+    /// This is an associated function, so that it doesn't hide a method
+    /// of the same name on `T`. Call it as `FreezeBox::into_box(x)`.
+    pub fn into_box(this: Self) -> Option<Box<T>> {
+        let ptr = this.inner.load(Ordering::Acquire);
+        // SAFETY: we own `this`, so no references to the info exist.
+        unsafe { this.info.reset() };
         // Prevent Drop::drop() from being called on the FreezeBox
         // because we are transferring ownership elsewhere.
-        mem::forget(self);
+        mem::forget(this);
         if ptr.is_null() {
             return None;
         }
 
-        // SAFETY: because we are consuming self, we must have sole ownership
+        // SAFETY: because we are consuming `this`, we must have sole ownership
         // of the FreezeBox contents. `lazy_init` created `ptr` from an
-        // owning `Box<T>`, so it's safe for us to recreate that Box and drop
-        // it.
+        // owning `Box<T>`, so it's safe for us to recreate that Box and hand
+        // it to the caller.
+        Some(unsafe { Box::from_raw(ptr) })
+    }
 
-        let tmp_box = unsafe { Box::from_raw(ptr) };
-        Some(*tmp_box)
+    /// Consume the `FreezeBox` and leak its contents, returning a mutable
+    /// reference that lives for the rest of the program.
+    ///
+    /// Like [`Box::leak`], this is intended for values that are never
+    /// dropped, such as singletons built during startup. Returns `None` if
+    /// the `FreezeBox` is uninitialized.
+    ///
+    /// This is an associated function, so that it doesn't hide a method
+    /// of the same name on `T`. Call it as `FreezeBox::leak(x)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let config = FreezeBox::<String>::default();
+    /// config.lazy_init("verbose".to_string());
+    /// let config: &'static str = FreezeBox::leak(config).unwrap();
+    /// assert_eq!(config, "verbose");
+    /// ```
+    pub fn leak<'a>(this: Self) -> Option<&'a mut T>
+    where
+        T: 'a,
+    {
+        Self::into_box(this).map(Box::leak)
+    }
+
+    /// Consume the `FreezeBox` and return its contents, or return the
+    /// `FreezeBox` itself if it is uninitialized.
+    ///
+    /// This is like `Arc::try_unwrap`: an empty container is handed back
+    /// to the caller rather than being dropped.
+    pub fn try_into_inner(mut self) -> Result<T, Self> {
+        if self.inner.get_mut().is_null() {
+            return Err(self);
+        }
+        Ok(self.into_inner().unwrap())
+    }
+
+    /// Consume the `FreezeBox` and return its contents, or `default` if it is
+    /// uninitialized.
+    pub fn into_inner_or(self, default: T) -> T {
+        self.into_inner().unwrap_or(default)
+    }
+
+    /// Consume the `FreezeBox` and return its contents, or the result of
+    /// calling `f` if it is uninitialized.
+    pub fn into_inner_or_else<F>(self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.into_inner().unwrap_or_else(f)
+    }
+}
+
+impl<T> FreezeBox<T> {
+    /// Get a read handle that caches the pointer to the inner value.
+    ///
+    /// Every deref of a `FreezeBox` performs an atomic load and a null
+    /// check. A [`FrozenRef`] performs them once, when it is created, so
+    /// later reads are plain pointer loads. This can help in tight loops.
+    ///
+    /// # Panics
+    ///
+    /// `reader` will panic if the `FreezeBox` is uninitialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let x = FreezeBox::<Vec<u32>>::default();
+    /// x.lazy_init(vec![1, 2, 3]);
+    ///
+    /// let reader = x.reader();
+    /// let sum: u32 = (0..3).map(|i| reader[i]).sum();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn reader(&self) -> FrozenRef<'_, T> {
+        FrozenRef { value: self }
+    }
+}
+
+/// A read handle for an initialized [`FreezeBox`].
+///
+/// Created by [`FreezeBox::reader`]. A `FrozenRef` derefs to the inner
+/// value without any atomic operations.
+pub struct FrozenRef<'a, T> {
+    value: &'a T,
+}
+
+impl<'a, T> FrozenRef<'a, T> {
+    /// Get a reference to the value, with the lifetime of the `FreezeBox`.
+    ///
+    /// This is an associated function, so that it doesn't hide a `get`
+    /// method on `T`. Call it as `FrozenRef::get(&reader)`.
+    pub fn get(this: &Self) -> &'a T {
+        this.value
+    }
+}
+
+impl<T> Clone for FrozenRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FrozenRef<'_, T> {}
+
+impl<T> Deref for FrozenRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FrozenRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrozenRef").field(self.value).finish()
     }
 }
 
@@ -204,51 +740,414 @@ impl<T> Deref for FreezeBox<T> {
         //    lives.
 
         let inner_ref = unsafe { inner.as_ref() };
-        inner_ref.unwrap_or_else(|| {
-            panic!(
-                "attempted to deref uninitialized FreezeBox<{}>",
-                type_name::<T>(),
-            )
-        })
+        inner_ref
+            .unwrap_or_else(|| panic_with_type!("attempted to deref uninitialized FreezeBox", T))
     }
 }
 
-impl<T> Default for FreezeBox<T> {
-    fn default() -> Self {
-        Self {
-            inner: AtomicPtr::default(),
-            phantom: PhantomData,
+impl<T> DerefMut for FreezeBox<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match Self::get_mut(self) {
+            Some(val) => val,
+            None => panic_with_type!("attempted to deref_mut uninitialized FreezeBox", T),
         }
     }
 }
 
-impl<T> Drop for FreezeBox<T> {
-    fn drop(&mut self) {
-        // We have exclusive access to the container, so this doesn't need
-        // to be atomic.
-        let inner = self.inner.get_mut();
+impl<T: Deref> FreezeBox<T> {
+    /// `Deref` the contents of the `FreezeBox`.
+    ///
+    /// This is helpful when you want the `Deref` form of the
+    /// data in the `FreezeBox`. For example, when called on a
+    /// `FreezeBox<String>`, this will return `&str`.
+    ///
+    /// # Panics
+    ///
+    /// `as_deref` will panic if the `FreezeBox` is uninitialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// fn greet(name: &str) -> String {
+    ///     format!("hello, {}", name)
+    /// }
+    ///
+    /// let name = FreezeBox::new(Some("world".to_string()));
+    /// assert_eq!(greet(name.as_deref()), "hello, world");
+    /// ```
+    pub fn as_deref(&self) -> &T::Target {
+        (**self).deref()
+    }
+}
 
-        if !inner.is_null() {
-            // We own an inner object.  Re-hydrate into a Box<T> so that
-            // T's destructor may run.
-            //
-            // SAFETY: We have exclusive access to the inner value, so we can
-            // safely drop the contents. We could also reset the pointer, but
-            // since this data structure is being dropped, this is the last
-            // time that pointer will be seen; so there's no point.
+impl<T: Default> FreezeBox<T> {
+    /// Initialize a `FreezeBox` with the default value of `T`.
+    ///
+    /// This is a shortcut for `lazy_init(T::default())` that returns a
+    /// reference to the new value.
+    ///
+    /// # Panics
+    ///
+    /// `init_with_default` will panic if the `FreezeBox` is already
+    /// initialized.
+    pub fn init_with_default(&self) -> &T {
+        self.lazy_init(T::default())
+    }
+}
 
-            let _owned = unsafe { Box::<T>::from_raw(*inner) };
+#[cfg(feature = "test-support")]
+impl<T> FreezeBox<T> {
+    /// Reset a `FreezeBox` to the uninitialized state, dropping its contents.
+    ///
+    /// This is intended for tests that share a `static` `FreezeBox`, so that
+    /// each test can start with an uninitialized container. It should
+    /// never be used outside of tests.
+    ///
+    /// This method requires the `test-support` feature.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no references to the contents exist
+    /// (in any thread), and that no other thread is accessing the `FreezeBox`
+    /// while it is being reset.
+    pub unsafe fn reset_for_testing(&self) {
+        self.reset();
+    }
+}
+
+#[cfg(any(feature = "test-support", feature = "exit-cleanup"))]
+impl<T> FreezeBox<T> {
+    /// Reset to the uninitialized state, dropping the contents.
+    ///
+    /// # Safety
+    ///
+    /// No references to the contents may exist, and no other thread may
+    /// be accessing the `FreezeBox`.
+    unsafe fn reset(&self) {
+        self.info.reset();
+        let ptr = self.inner.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !ptr.is_null() {
+            // SAFETY: `ptr` was created by `Box::into_raw` in `lazy_init`,
+            // and we just removed it from the container. The caller
+            // guarantees that no references to the contents are alive.
+            let _owned = Box::<T>::from_raw(ptr);
+            crate::observer::observe_free::<T>();
             // _owned will drop here.
         }
     }
 }
 
-/// Must fail to compile because FreezeBox<Rc> must not be Send.
-/// ```compile_fail
-/// use freezebox::FreezeBox;
-/// use std::rc::Rc;
-///
-/// fn require_send<T: Send>(_t: &T) {}
+#[cfg(feature = "exit-cleanup")]
+impl<T: Send + Sync + 'static> FreezeBox<T> {
+    /// Drop the contents of this `FreezeBox` when the process exits.
+    ///
+    /// The contents of a `static` are normally never dropped, so values
+    /// like files and sockets are never flushed or closed. Registered
+    /// containers are reset by [`run_exit_cleanups`], in reverse order of
+    /// registration. On unix platforms, `run_exit_cleanups` is also
+    /// called automatically when the process exits normally (via
+    /// `atexit`).
+    ///
+    /// This method requires the `exit-cleanup` feature.
+    ///
+    /// [`run_exit_cleanups`]: crate::run_exit_cleanups
+    ///
+    /// # Safety
+    ///
+    /// The contents must not be used after the process starts exiting:
+    /// no other thread may still be using them, and they must not be
+    /// accessed by destructors or other exit handlers that run later.
+    pub unsafe fn register_exit_cleanup(&'static self) {
+        crate::exit::register(self);
+    }
+}
+
+#[cfg(feature = "exit-cleanup")]
+impl<T: Send + Sync> crate::exit::ExitCleanup for FreezeBox<T> {
+    unsafe fn exit_cleanup(&self) {
+        self.reset();
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl<T: Send + Sync + 'static> FreezeBox<T> {
+    /// Make this `FreezeBox` uninitialized in the child process after `fork()`.
+    ///
+    /// Values like thread pools and sockets are often unusable in a forked
+    /// child. Registering a `FreezeBox` with `clear_on_fork` returns it to the
+    /// uninitialized state in the child, so that it can be initialized
+    /// again.
+    ///
+    /// The old value is leaked rather than dropped, because its destructor
+    /// may not be safe to run in the child, and because references to it
+    /// may still exist.
+    ///
+    /// This method requires the `std` feature, and is only available on
+    /// unix platforms.
+    pub fn clear_on_fork(&'static self) {
+        crate::fork::register(self);
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl<T: Send + Sync> crate::fork::ForkClear for FreezeBox<T> {
+    fn fork_clear(&self) {
+        // The old value is leaked, so any existing references to it
+        // remain valid.
+        self.inner.store(ptr::null_mut(), Ordering::Release);
+        self.info.leak();
+    }
+}
+
+impl<T: PartialEq> FreezeBox<T> {
+    /// Initialize a `FreezeBox`, tolerating re-initialization with an equal value.
+    ///
+    /// This behaves like [`lazy_init`][`FreezeBox::lazy_init`], except that if the
+    /// `FreezeBox` is already initialized with a value equal to `val`, then `val`
+    /// is dropped and the call succeeds. This is useful when multiple code
+    /// paths may legitimately compute the same value.
+    ///
+    /// Returns a reference to the stored value, which is either `val` or the
+    /// equal value that was already there.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let x = FreezeBox::<String>::default();
+    /// assert_eq!(x.lazy_init_idempotent("hello".to_string()), "hello");
+    /// assert_eq!(x.lazy_init_idempotent("hello".to_string()), "hello");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_idempotent` will panic if the `FreezeBox` is already
+    /// initialized with a different value. If it panics, the input value will
+    /// be dropped.
+    pub fn lazy_init_idempotent(&self, val: T) -> &T {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+
+        // The atomic ordering here is the same as `lazy_init`.
+        crate::chaos::delay();
+        if let Err(existing) =
+            self.inner
+                .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+        {
+            // SAFETY: `ptr` was just created above using `Box::into_raw`,
+            // and because compare_exchange failed, we are still its unique
+            // owner.
+            let val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+
+            // SAFETY: compare_exchange failed, so `existing` is a non-null
+            // pointer that was published by a successful initialization. It
+            // remains valid for as long as `self` lives.
+            let existing = unsafe { &*existing };
+
+            if *existing != *val {
+                panic_with_type!(
+                    "lazy_init_idempotent with a different value on already-initialized FreezeBox",
+                    T
+                );
+            }
+            existing
+        } else {
+            self.info.record();
+            // SAFETY: we just published `ptr`, and the value can't be removed
+            // while `self` is shared.
+            unsafe { &*ptr }
+        }
+    }
+}
+
+impl<T> FreezeBox<T> {
+    /// Initialize a `FreezeBox`, if the value passes a validation check.
+    ///
+    /// `check` is called on `val` before it is published. If `check`
+    /// returns an error, the `FreezeBox` is unchanged, and the error is
+    /// returned along with `val`. This ensures that readers can never
+    /// observe a value that violates an invariant.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_validated` will panic if the check passes but the
+    /// `FreezeBox` is already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let limit = FreezeBox::<u32>::default();
+    /// let nonzero = |x: &u32| if *x == 0 { Err("zero") } else { Ok(()) };
+    ///
+    /// let err = limit.lazy_init_validated(0, nonzero).unwrap_err();
+    /// assert_eq!(*err.error(), "zero");
+    /// assert!(!limit.is_initialized());
+    ///
+    /// assert_eq!(limit.lazy_init_validated(100, nonzero), Ok(&100));
+    /// assert_eq!(*limit, 100);
+    /// ```
+    pub fn lazy_init_validated<E, F>(&self, val: T, check: F) -> Result<&T, InitError<E, T>>
+    where
+        F: FnOnce(&T) -> Result<(), E>,
+    {
+        if let Err(e) = check(&val) {
+            return Err(InitError::new(e, val));
+        }
+        Ok(self.lazy_init(val))
+    }
+}
+
+impl<T: Copy> FreezeBox<T> {
+    /// Get a copy of the value in the `FreezeBox`.
+    ///
+    /// Returns `None` if the `FreezeBox` is uninitialized. This is convenient
+    /// for small values like integers, where a reference isn't needed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// static PORT: FreezeBox<u16> = FreezeBox::const_default();
+    /// assert_eq!(PORT.get_copy(), None);
+    /// PORT.lazy_init(8080);
+    /// assert_eq!(PORT.get_copy(), Some(8080));
+    /// ```
+    pub fn get_copy(&self) -> Option<T> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or points to a value that
+        // lives as long as `self`.
+        unsafe { ptr.as_ref() }.copied()
+    }
+
+    /// Get a copy of the value in the `FreezeBox`.
+    ///
+    /// This is the same as [`get_copy`][FreezeBox::get_copy], named to match
+    /// `Option::copied`.
+    pub fn get_copied(&self) -> Option<T> {
+        self.get_copy()
+    }
+}
+
+impl<T: Clone> FreezeBox<T> {
+    /// Get a clone of the value in the `FreezeBox`.
+    ///
+    /// Returns `None` if the `FreezeBox` is uninitialized. This is the same as
+    /// `get().cloned()`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let name = FreezeBox::<String>::default();
+    /// assert_eq!(name.get_cloned(), None);
+    /// name.lazy_init("hello".to_string());
+    /// assert_eq!(name.get_cloned(), Some("hello".to_string()));
+    /// ```
+    pub fn get_cloned(&self) -> Option<T> {
+        Self::get(self).cloned()
+    }
+
+    /// Initialize a `FreezeBox` with a clone of `val`.
+    ///
+    /// This is useful when the caller only has a reference to the value.
+    /// Unlike `lazy_init(val.clone())`, the clone is skipped if the
+    /// `FreezeBox` is already initialized.
+    /// Returns a reference to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_cloned` will panic if the `FreezeBox` is already
+    /// initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let default_name = "default".to_string();
+    /// let slot = FreezeBox::<String>::default();
+    /// slot.lazy_init_cloned(&default_name);
+    /// assert_eq!(*slot, "default");
+    /// ```
+    pub fn lazy_init_cloned(&self, val: &T) -> &T {
+        if self.is_initialized() {
+            panic_with_type!("lazy_init on already-initialized FreezeBox", T);
+        }
+        self.lazy_init(val.clone())
+    }
+}
+
+impl<T: FromStr> FreezeBox<T> {
+    /// Initialize a `FreezeBox` by parsing a string.
+    ///
+    /// This is a shortcut for parsing `s` and passing the result to
+    /// `lazy_init`, which is handy for configuration values that come from
+    /// the environment or the command line. If parsing fails, the error is
+    /// returned and the `FreezeBox` is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_parse` will panic if parsing succeeds but the `FreezeBox`
+    /// is already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// let port = FreezeBox::<u16>::default();
+    /// assert!(port.lazy_init_parse("eighty").is_err());
+    /// assert_eq!(port.lazy_init_parse("80"), Ok(&80));
+    /// ```
+    pub fn lazy_init_parse(&self, s: &str) -> Result<&T, T::Err> {
+        Ok(self.lazy_init(s.parse()?))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FreezeBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or points to a value that
+        // lives as long as `self`; see `Deref`.
+        match unsafe { ptr.as_ref() } {
+            Some(val) => f.debug_tuple("FreezeBox").field(val).finish(),
+            None => f.write_str("FreezeBox(<uninitialized>)"),
+        }
+    }
+}
+
+impl<T> Default for FreezeBox<T> {
+    fn default() -> Self {
+        Self {
+            inner: AtomicPtr::default(),
+            info: InfoSlot::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for FreezeBox<T> {
+    fn drop(&mut self) {
+        // We have exclusive access to the container, so this doesn't need
+        // to be atomic.
+        let inner = self.inner.get_mut();
+
+        if !inner.is_null() {
+            // We own an inner object.  Re-hydrate into a Box<T> so that
+            // T's destructor may run.
+            //
+            // SAFETY: We have exclusive access to the inner value, so we can
+            // safely drop the contents. We could also reset the pointer, but
+            // since this data structure is being dropped, this is the last
+            // time that pointer will be seen; so there's no point.
+
+            let _owned = unsafe { Box::<T>::from_raw(*inner) };
+            crate::observer::observe_free::<T>();
+            // _owned will drop here.
+        }
+    }
+}
+
+/// Must fail to compile because FreezeBox<Rc> must not be Send.
+/// ```compile_fail
+/// use freezebox::FreezeBox;
+/// use std::rc::Rc;
+///
+/// fn require_send<T: Send>(_t: &T) {}
 ///
 /// let x = FreezeBox::<Rc<u32>>::new();
 /// require_send(&x); // <- must fail to compile.
@@ -268,10 +1167,14 @@ struct _Unused; // Only exists to get the compile-fail doctest
 
 #[cfg(test)]
 mod tests {
-    use super::FreezeBox;
+    use super::{FreezeBox, FrozenRef, MaybeBox};
+    use alloc::boxed::Box;
+    use alloc::format;
     use alloc::string::String;
     use alloc::string::ToString;
     use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
 
     #[test]
     fn freezebox_test() {
@@ -320,10 +1223,446 @@ mod tests {
         assert_eq!(y2, None);
     }
 
+    #[test]
+    fn try_into_inner_test() {
+        let x = FreezeBox::<String>::default();
+        let y = x.try_into_inner().unwrap_err();
+        y.lazy_init("hello".to_string());
+        assert_eq!(*y, "hello");
+        assert_eq!(y.try_into_inner().ok().unwrap(), "hello");
+    }
+
+    #[test]
+    fn into_inner_or_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.into_inner_or("default".to_string()), "default");
+        let x = FreezeBox::new(Some("hello".to_string()));
+        assert_eq!(x.into_inner_or("default".to_string()), "hello");
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.into_inner_or_else(String::new), "");
+        let x = FreezeBox::new(Some("hello".to_string()));
+        assert_eq!(x.into_inner_or_else(|| unreachable!()), "hello");
+    }
+
+    #[test]
+    fn init_unchecked_test() {
+        static X: FreezeBox<String> = FreezeBox::const_default();
+        // SAFETY: X is uninitialized, and no other thread can see it.
+        unsafe { X.init_unchecked("hello".to_string()) };
+        assert_eq!(*X, "hello");
+    }
+
+    #[test]
+    fn with_test() {
+        let x = FreezeBox::new(Some("hello".to_string()));
+        assert_eq!(x.with(|s| s.len()), 5);
+    }
+
+    #[test]
+    fn lazy_init_parse_test() {
+        let x = FreezeBox::<u32>::default();
+        assert!(x.lazy_init_parse("-1").is_err());
+        assert!(!x.is_initialized());
+        assert_eq!(x.lazy_init_parse("42"), Ok(&42));
+        assert_eq!(*x, 42);
+    }
+
+    #[test]
+    fn lazy_init_validated_test() {
+        let x = FreezeBox::<String>::default();
+        let not_empty = |s: &String| if s.is_empty() { Err("empty") } else { Ok(()) };
+        let err = x.lazy_init_validated(String::new(), not_empty).unwrap_err();
+        assert_eq!(err.into_parts(), ("empty", String::new()));
+        assert!(!x.is_initialized());
+        assert_eq!(
+            x.lazy_init_validated("hello".to_string(), not_empty)
+                .unwrap(),
+            "hello"
+        );
+        assert_eq!(*x, "hello");
+    }
+
+    #[test]
+    fn get_test() {
+        let x = FreezeBox::<Vec<u32>>::default();
+        assert_eq!(FreezeBox::get(&x), None);
+        x.lazy_init(vec![1, 2]);
+        assert_eq!(FreezeBox::get(&x), Some(&vec![1, 2]));
+        assert_eq!(x.get(1), Some(&2));
+    }
+
+    #[test]
+    fn try_lazy_init_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.try_lazy_init("hello".to_string()).unwrap(), "hello");
+        assert_eq!(
+            x.try_lazy_init("goodbye".to_string()),
+            Err("goodbye".to_string())
+        );
+        assert_eq!(*x, "hello");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn get_or_init_test() {
+        let x = Arc::new(FreezeBox::<usize>::default());
+        let threads: Vec<_> = (0..8)
+            .map(|n| {
+                let x = x.clone();
+                std::thread::spawn(move || *x.get_or_init(|| n))
+            })
+            .collect();
+        let results: Vec<usize> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        let winner = *x.get_or_init(|| unreachable!());
+        assert!(results.iter().all(|&n| n == winner));
+    }
+
+    #[test]
+    fn get_mut_test() {
+        let mut x = FreezeBox::<String>::default();
+        assert!(FreezeBox::get_mut(&mut x).is_none());
+        x.lazy_init("hello".to_string());
+        FreezeBox::get_mut(&mut x).unwrap().push_str(" world");
+        assert_eq!(FreezeBox::get(&x).unwrap(), "hello world");
+    }
+
+    #[test]
+    fn deref_mut_test() {
+        let mut x = FreezeBox::new(Some(vec![1, 2]));
+        x.push(3);
+        x[0] = 0;
+        assert_eq!(*x, [0, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempted to deref_mut uninitialized FreezeBox")]
+    fn deref_mut_uninit_test() {
+        let mut x = FreezeBox::<Vec<u32>>::default();
+        x.push(1);
+    }
+
+    #[test]
+    fn take_test() {
+        let mut x = FreezeBox::<String>::default();
+        assert_eq!(FreezeBox::take(&mut x), None);
+        x.lazy_init("first".to_string());
+        assert_eq!(FreezeBox::take(&mut x).unwrap(), "first");
+        assert!(!x.is_initialized());
+        x.lazy_init("second".to_string());
+        assert_eq!(FreezeBox::get(&x).unwrap(), "second");
+    }
+
+    #[test]
+    fn deref_methods_not_hidden_test() {
+        // Methods on `T` with the same names as `FreezeBox` associated
+        // functions must still be reachable through `Deref`.
+        let x = FreezeBox::new(Some("a-a".to_string()));
+        assert_eq!(x.replace("-", "+"), "a+a");
+        let mut y = FreezeBox::new(Some(Some(1)));
+        assert_eq!(y.take(), Some(1));
+        assert_eq!(*y, None);
+    }
+
+    #[test]
+    fn replace_test() {
+        let value = Arc::new(());
+        let mut x = FreezeBox::<Arc<()>>::default();
+        assert!(FreezeBox::replace(&mut x, value.clone()).is_none());
+        assert_eq!(Arc::strong_count(&value), 2);
+        assert!(FreezeBox::replace(&mut x, value.clone()).is_some());
+        assert_eq!(Arc::strong_count(&value), 2);
+    }
+
+    #[test]
+    fn into_box_test() {
+        let x = FreezeBox::<[u8; 4]>::default();
+        assert!(FreezeBox::into_box(x).is_none());
+        let x = FreezeBox::new(Some([1, 2, 3, 4]));
+        assert_eq!(FreezeBox::into_box(x), Some(Box::new([1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn lazy_init_box_test() {
+        let x = FreezeBox::<String>::default();
+        let boxed = Box::new("hello".to_string());
+        let addr: *const String = &*boxed;
+        assert!(core::ptr::eq(x.lazy_init_box(boxed), addr));
+        assert_eq!(FreezeBox::get(&x).unwrap(), "hello");
+    }
+
+    #[test]
+    fn leak_test() {
+        assert!(FreezeBox::leak(FreezeBox::<u32>::default()).is_none());
+        let x: &'static mut u32 = FreezeBox::leak(FreezeBox::new(Some(1))).unwrap();
+        *x += 1;
+        assert_eq!(*x, 2);
+    }
+
+    #[test]
+    fn deref_expect_test() {
+        let x = FreezeBox::new(Some(1));
+        assert_eq!(*x.deref_expect("x"), 1);
+    }
+
+    #[cfg(not(feature = "static-panic-messages"))]
+    #[test]
+    #[should_panic(expected = "retry limit not loaded: uninitialized FreezeBox")]
+    fn panic_deref_expect() {
+        let x = FreezeBox::<u32>::default();
+        x.deref_expect("retry limit not loaded");
+    }
+
+    #[cfg(all(feature = "std", feature = "static-panic-messages"))]
+    #[test]
+    fn static_panic_deref_expect() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let x = FreezeBox::<u32>::default();
+        let err = catch_unwind(AssertUnwindSafe(|| {
+            *x.deref_expect("retry limit not loaded")
+        }));
+        let payload = err.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<&'static str>(),
+            Some(&"uninitialized FreezeBox")
+        );
+    }
+
+    #[test]
+    fn deref_or_test() {
+        let x = FreezeBox::<u32>::default();
+        assert_eq!(*x.deref_or(&7), 7);
+        x.lazy_init(1);
+        assert_eq!(*x.deref_or(&7), 1);
+    }
+
+    #[test]
+    fn lazy_init_ref_test() {
+        let x = FreezeBox::<String>::default();
+        let r = x.lazy_init("hello".to_string());
+        assert_eq!(r, "hello");
+        assert!(core::ptr::eq(r, FreezeBox::get(&x).unwrap()));
+    }
+
+    #[test]
+    fn race_init_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.race_init("first".to_string()), "first");
+        assert_eq!(x.race_init("second".to_string()), "first");
+    }
+
+    #[test]
+    fn get_or_try_init_test() {
+        let x = FreezeBox::<u32>::default();
+        assert_eq!(x.get_or_try_init(|| Err("nope")), Err("nope"));
+        assert!(!x.is_initialized());
+        assert_eq!(x.get_or_try_init(|| Ok::<_, ()>(1)), Ok(&1));
+        assert_eq!(x.get_or_try_init(|| Err(())), Ok(&1));
+    }
+
+    #[test]
+    fn get_or_try_init_retries_test() {
+        let x = FreezeBox::<u32>::default();
+        let mut calls = 0;
+        for _ in 0..3 {
+            let r = x.get_or_try_init(|| {
+                calls += 1;
+                Err(calls)
+            });
+            assert_eq!(r, Err(calls));
+        }
+        assert_eq!(calls, 3);
+        assert!(!x.is_initialized());
+    }
+
+    #[test]
+    fn get_or_try_init_cached_test() {
+        let x = FreezeBox::<u32>::default();
+        let errors = MaybeBox::default();
+        assert_eq!(
+            x.get_or_try_init_cached(&errors, || Err("nope")),
+            Err(&"nope")
+        );
+        assert_eq!(x.get_or_try_init_cached(&errors, || Ok(1)), Err(&"nope"));
+        assert!(!x.is_initialized());
+
+        let y = FreezeBox::<u32>::default();
+        let errors = MaybeBox::<&str>::default();
+        assert_eq!(y.get_or_try_init_cached(&errors, || Ok(1)), Ok(&1));
+        assert_eq!(y.get_or_try_init_cached(&errors, || Err("nope")), Ok(&1));
+        assert!(!errors.is_initialized());
+    }
+
+    #[test]
+    fn get_copy_test() {
+        let x = FreezeBox::<u32>::default();
+        assert_eq!(x.get_copy(), None);
+        x.lazy_init(5);
+        assert_eq!(x.get_copy(), Some(5));
+        assert_eq!(x.get_copied(), Some(5));
+    }
+
+    #[test]
+    fn get_cloned_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.get_cloned(), None);
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.get_cloned().unwrap(), "hello");
+    }
+
+    #[test]
+    fn lazy_init_cloned_test() {
+        let x = FreezeBox::<String>::default();
+        let val = "hello".to_string();
+        x.lazy_init_cloned(&val);
+        assert_eq!(*x, val);
+    }
+
+    #[test]
+    #[should_panic(expected = "lazy_init on already-initialized FreezeBox")]
+    fn lazy_init_cloned_initialized_test() {
+        struct NoClone;
+        impl Clone for NoClone {
+            fn clone(&self) -> Self {
+                unreachable!()
+            }
+        }
+        let x = FreezeBox::new(Some(NoClone));
+        x.lazy_init_cloned(&NoClone);
+    }
+
+    #[test]
+    fn assert_initialized_test() {
+        let x = FreezeBox::new(Some("hello".to_string()));
+        x.assert_initialized("config phase");
+    }
+
+    #[cfg(not(feature = "static-panic-messages"))]
+    #[test]
+    #[should_panic(expected = "config phase: uninitialized FreezeBox")]
+    fn panic_assert_initialized() {
+        let x = FreezeBox::<String>::default();
+        x.assert_initialized("config phase");
+    }
+
+    #[cfg(all(feature = "std", feature = "static-panic-messages"))]
+    #[test]
+    fn static_panic_assert_initialized() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let x = FreezeBox::<String>::default();
+        let err = catch_unwind(AssertUnwindSafe(|| x.assert_initialized("config phase")));
+        let payload = err.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<&'static str>(),
+            Some(&"uninitialized FreezeBox")
+        );
+    }
+
+    #[test]
+    fn as_maybe_test() {
+        let x = FreezeBox::<String>::default();
+        assert!(x.as_maybe().get().is_none());
+        x.as_maybe().lazy_init("hello".to_string());
+        assert_eq!(*x, "hello");
+        assert_eq!(x.as_maybe().get().unwrap(), "hello");
+    }
+
+    #[cfg(feature = "init-info")]
+    #[test]
+    fn init_info_test() {
+        let x = FreezeBox::<String>::default();
+        assert!(x.init_info().is_none());
+        let handle = std::thread::Builder::new()
+            .name("initializer".to_string())
+            .spawn(move || {
+                x.lazy_init("hello".to_string());
+                x
+            })
+            .unwrap();
+        let initializer = handle.thread().id();
+        let x = handle.join().unwrap();
+        let info = x.init_info().unwrap();
+        assert_eq!(info.thread_id(), initializer);
+        assert_eq!(info.thread_name(), Some("initializer"));
+    }
+
     #[test]
     fn const_test() {
         static X: FreezeBox<String> = FreezeBox::const_default();
         X.lazy_init("hello".to_string());
         assert_eq!(*X, "hello");
     }
+
+    #[test]
+    fn idempotent_init() {
+        let x = FreezeBox::<String>::default();
+        let first = x.lazy_init_idempotent("hello".to_string());
+        let second = x.lazy_init_idempotent("hello".to_string());
+        assert!(core::ptr::eq(first, second));
+        assert_eq!(*x, "hello");
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_idempotent_mismatch() {
+        let x = FreezeBox::<String>::default();
+        x.lazy_init_idempotent("first".to_string());
+        x.lazy_init_idempotent("second".to_string());
+    }
+
+    #[test]
+    fn debug_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(format!("{:?}", x), "FreezeBox(<uninitialized>)");
+        x.lazy_init("hello".to_string());
+        assert_eq!(format!("{:?}", x), "FreezeBox(\"hello\")");
+    }
+
+    #[test]
+    fn as_deref_test() {
+        let x = FreezeBox::<String>::default();
+        x.lazy_init("hello".to_string());
+        let s: &str = x.as_deref();
+        assert_eq!(s, "hello");
+    }
+
+    #[test]
+    fn init_with_default_test() {
+        let x = FreezeBox::<String>::default();
+        assert_eq!(x.init_with_default(), "");
+        assert_eq!(*x, "");
+    }
+
+    #[test]
+    fn reader_test() {
+        let x = FreezeBox::<String>::default();
+        x.lazy_init("hello".to_string());
+        let reader = x.reader();
+        let copy = reader;
+        assert_eq!(*reader, "hello");
+        assert_eq!(FrozenRef::get(&copy), "hello");
+        // `reader.get` is still `str::get`.
+        assert_eq!(reader.get(0..1), Some("h"));
+        assert_eq!(format!("{:?}", reader), "FrozenRef(\"hello\")");
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_reader() {
+        let x = FreezeBox::<String>::default();
+        let _reader = x.reader();
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn reset_test() {
+        static X: FreezeBox<String> = FreezeBox::const_default();
+        X.lazy_init("first".to_string());
+        // SAFETY: no references to the contents exist.
+        unsafe { X.reset_for_testing() };
+        assert!(!X.is_initialized());
+        X.lazy_init("second".to_string());
+        assert_eq!(*X, "second");
+    }
 }
@@ -0,0 +1,416 @@
+//! This is the FrozenIndexMap implementation.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `FrozenIndexMap` is an insertion-ordered map of insert-once entries.
+///
+/// Entries can be inserted using only a shared reference, and references
+/// to inserted keys and values remain valid for as long as the
+/// `FrozenIndexMap` lives. Entries can never be removed, except by
+/// consuming the map with `into_vec()`.
+///
+/// Entries are kept in the order they were inserted, and can be looked up
+/// by key or by position. This makes iteration order deterministic, which
+/// is useful for registries that generate code or other reproducible
+/// output.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::FrozenIndexMap;
+///
+/// let registry = FrozenIndexMap::<String, u32>::default();
+/// let zebra: &u32 = registry.insert("zebra".to_string(), 26).unwrap();
+/// registry.insert("apple".to_string(), 1).unwrap();
+///
+/// // Duplicate keys are rejected.
+/// assert!(registry.insert("zebra".to_string(), 0).is_err());
+///
+/// assert_eq!(*zebra, 26);
+/// assert_eq!(registry.get("apple"), Some(&1));
+/// assert_eq!(registry.get_index(0).unwrap().0, "zebra");
+/// assert_eq!(registry.get_index_of("apple"), Some(1));
+/// ```
+pub struct FrozenIndexMap<K, V, S = RandomState> {
+    inner: Mutex<Inner<K, V>>,
+    hasher: S,
+    phantom: PhantomData<(K, V)>,
+}
+
+struct Inner<K, V> {
+    /// Pointers created by `Box::into_raw`, in insertion order.
+    ///
+    /// Raw pointers are used instead of `Box`, for the same reason as in
+    /// `FrozenSortedVec`: moving a `Box` would invalidate the shared
+    /// references we have handed out.
+    entries: Vec<*mut (K, V)>,
+    /// Entry positions, indexed by the hash of their key.
+    ///
+    /// Indexing by hash means we don't need to store a second copy of
+    /// each key.
+    index: HashMap<u64, Vec<usize>>,
+}
+
+impl<K, V, S> FrozenIndexMap<K, V, S> {
+    /// Create an empty `FrozenIndexMap` that uses the given hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                entries: Vec::new(),
+                index: HashMap::new(),
+            }),
+            hasher,
+            phantom: PhantomData,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<K, V>> {
+        // The lock is only held while running `Hash` and `Eq` impls, or
+        // the closure passed to `get_or_insert_with`, and none of them can
+        // leave the map inconsistent even if they panic.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a reference to an entry.
+    fn entry_ref(&self, ptr: *mut (K, V)) -> (&K, &V) {
+        // SAFETY: every pointer in the map was created by
+        // `Box::into_raw`, and is only freed when `self` is consumed or
+        // dropped.
+        let (k, v) = unsafe { &*ptr };
+        (k, v)
+    }
+
+    /// Return the entry at a given position in insertion order.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        let inner = self.lock();
+        inner.entries.get(index).map(|&e| self.entry_ref(e))
+    }
+
+    /// Return the number of entries.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Test whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// Iterate over the keys, in insertion order.
+    ///
+    /// Entries inserted while iterating will be included.
+    pub fn keys(&self) -> Keys<'_, K, V, S> {
+        Keys {
+            map: self,
+            index: 0,
+        }
+    }
+
+    /// Consume the map and return its entries, in insertion order.
+    pub fn into_vec(mut self) -> Vec<(K, V)> {
+        let inner = self.inner.get_mut().unwrap_or_else(PoisonError::into_inner);
+        // Take the pointers, so that `Drop` doesn't free them again.
+        let entries = core::mem::take(&mut inner.entries);
+
+        // SAFETY: every pointer was created by `Box::into_raw`, and we
+        // have exclusive access.
+        entries
+            .into_iter()
+            .map(|e| *unsafe { Box::from_raw(e) })
+            .collect()
+    }
+}
+
+impl<K, V, S> FrozenIndexMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Find the position of a key, with the lock held.
+    fn find<Q>(&self, inner: &Inner<K, V>, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let positions = inner.index.get(&hash)?;
+        positions
+            .iter()
+            .copied()
+            .find(|&i| self.entry_ref(inner.entries[i]).0.borrow() == key)
+    }
+
+    /// Insert an entry.
+    ///
+    /// If the key is already present, the new entry is returned in `Err`.
+    /// Otherwise, a reference to the inserted value is returned.
+    pub fn insert(&self, key: K, val: V) -> Result<&V, (K, V)> {
+        let hash = self.hash(&key);
+        let mut inner = self.lock();
+        if self.find(&inner, hash, &key).is_some() {
+            return Err((key, val));
+        }
+        let ptr = Box::into_raw(Box::new((key, val)));
+        let position = inner.entries.len();
+        inner.entries.push(ptr);
+        inner.index.entry(hash).or_default().push(position);
+        Ok(self.entry_ref(ptr).1)
+    }
+
+    /// Look up a value by a borrowed key, inserting a new entry if it is
+    /// missing.
+    ///
+    /// `key` is hashed only once, and the lookup and insert happen under
+    /// the same lock, so no other thread can insert the key in between.
+    /// `f` is only called if the key is missing, so a
+    /// `FrozenIndexMap<String, V>` can be probed with a `&str` without
+    /// allocating a `String` on every lookup.
+    ///
+    /// `f` runs while the map is locked, so it must not access the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key returned by `f` isn't equal to `key`.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::FrozenIndexMap;
+    ///
+    /// let interned = FrozenIndexMap::<String, usize>::default();
+    /// let id = *interned.get_or_insert_with("apple", || ("apple".to_string(), 0));
+    /// // The closure isn't called for a key that is already present.
+    /// assert_eq!(*interned.get_or_insert_with("apple", || unreachable!()), id);
+    /// ```
+    pub fn get_or_insert_with<Q, F>(&self, key: &Q, f: F) -> &V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce() -> (K, V),
+    {
+        let hash = self.hash(key);
+        let mut inner = self.lock();
+        if let Some(position) = self.find(&inner, hash, key) {
+            return self.entry_ref(inner.entries[position]).1;
+        }
+        let (new_key, val) = f();
+        assert!(
+            new_key.borrow() == key,
+            "get_or_insert_with: the new key doesn't match the lookup key"
+        );
+        let ptr = Box::into_raw(Box::new((new_key, val)));
+        let position = inner.entries.len();
+        inner.entries.push(ptr);
+        inner.index.entry(hash).or_default().push(position);
+        self.entry_ref(ptr).1
+    }
+
+    /// Look up a value by key.
+    ///
+    /// The key may be any borrowed form of the key type, like `&str` for
+    /// a `FrozenIndexMap<String, V>`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        self.get_index(index).map(|(_, v)| v)
+    }
+
+    /// Return the position of a key in insertion order.
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash(key);
+        let inner = self.lock();
+        self.find(&inner, hash, key)
+    }
+
+    /// Test whether a key is present.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index_of(key).is_some()
+    }
+}
+
+/// An iterator over the keys of a [`FrozenIndexMap`].
+///
+/// This is returned by [`FrozenIndexMap::keys`].
+pub struct Keys<'a, K, V, S> {
+    map: &'a FrozenIndexMap<K, V, S>,
+    index: usize,
+}
+
+impl<'a, K, V, S> Iterator for Keys<'a, K, V, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        let (key, _) = self.map.get_index(self.index)?;
+        self.index += 1;
+        Some(key)
+    }
+}
+
+impl<K, V, S: Default> Default for FrozenIndexMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Drop for FrozenIndexMap<K, V, S> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for &e in inner.entries.iter() {
+            // SAFETY: every pointer was created by `Box::into_raw`, and we
+            // have exclusive access.
+            let _owned = unsafe { Box::from_raw(e) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `FrozenIndexMap` owns its entries, like a `Vec<Box<(K, V)>>`.
+unsafe impl<K: Send, V: Send, S: Send> Send for FrozenIndexMap<K, V, S> {}
+
+// SAFETY: shared references to the entries are handed out to any thread,
+// and an entry inserted by one thread may be dropped by another.
+unsafe impl<K, V, S> Sync for FrozenIndexMap<K, V, S>
+where
+    K: Send + Sync,
+    V: Send + Sync,
+    S: Sync,
+{
+}
+
+/// Must fail to compile because FrozenIndexMap<_, Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::FrozenIndexMap;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = FrozenIndexMap::<u32, Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenIndexMap;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::hash::BuildHasherDefault;
+    use core::hash::Hasher;
+    use std::thread;
+
+    #[test]
+    fn indexmap_test() {
+        let x = FrozenIndexMap::<String, u32>::default();
+        assert!(x.is_empty());
+        let c = x.insert("c".to_string(), 3).unwrap();
+        let a = x.insert("a".to_string(), 1).unwrap();
+        let b = x.insert("b".to_string(), 2).unwrap();
+        assert_eq!(x.insert("b".to_string(), 0), Err(("b".to_string(), 0)));
+
+        // References survive later inserts.
+        assert_eq!((*a, *b, *c), (1, 2, 3));
+        assert_eq!(x.len(), 3);
+        assert!(x.contains_key("a"));
+        assert!(!x.contains_key("d"));
+        assert_eq!(x.get("b"), Some(&2));
+        assert_eq!(x.get_index(0), Some((&"c".to_string(), &3)));
+        assert_eq!(x.get_index_of("a"), Some(1));
+        assert!(x.get_index(3).is_none());
+
+        assert_eq!(x.keys().collect::<Vec<_>>(), ["c", "a", "b"]);
+
+        let keys: Vec<String> = x.into_vec().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn get_or_insert_with_test() {
+        let x = FrozenIndexMap::<String, u32>::default();
+        let a = x.get_or_insert_with("a", || ("a".to_string(), 1));
+        assert_eq!(*a, 1);
+        assert_eq!(*x.get_or_insert_with("a", || unreachable!()), 1);
+        assert_eq!(*x.get_or_insert_with("b", || ("b".to_string(), 2)), 2);
+        assert_eq!(x.get_index_of("b"), Some(1));
+        assert_eq!(x.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "the new key doesn't match the lookup key")]
+    fn get_or_insert_with_mismatch_test() {
+        let x = FrozenIndexMap::<String, u32>::default();
+        x.get_or_insert_with("a", || ("b".to_string(), 1));
+    }
+
+    /// A hasher that sends every key to the same bucket.
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn hash_collision_test() {
+        let x = FrozenIndexMap::<u32, u32, BuildHasherDefault<Collide>>::default();
+        for i in 0..10 {
+            x.insert(i, i * 10).unwrap();
+        }
+        assert!(x.insert(5, 0).is_err());
+        for i in 0..10 {
+            assert_eq!(x.get(&i), Some(&(i * 10)));
+        }
+    }
+
+    #[test]
+    fn concurrent_test() {
+        let x = Arc::new(FrozenIndexMap::<u32, u32>::default());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let x = x.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let _ = x.insert(i * 4 + t, t);
+                        let _ = x.insert(i, t);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(x.len(), 400);
+        for i in 0..400 {
+            let index = x.get_index_of(&i).unwrap();
+            assert_eq!(*x.get_index(index).unwrap().0, i);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Recording which thread initialized a container, and when.
+
+#[cfg(feature = "init-info")]
+use alloc::boxed::Box;
+#[cfg(feature = "init-info")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "init-info")]
+use core::ptr;
+#[cfg(feature = "init-info")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(feature = "init-info")]
+use std::thread::{self, ThreadId};
+#[cfg(feature = "init-info")]
+use std::time::SystemTime;
+
+/// Details about the initialization of a container.
+///
+/// This is returned by methods like [`FreezeBox::init_info`]. It requires
+/// the `init-info` feature.
+///
+/// [`FreezeBox::init_info`]: crate::FreezeBox::init_info
+#[cfg(feature = "init-info")]
+#[derive(Clone, Debug)]
+pub struct InitInfo {
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+    time: SystemTime,
+}
+
+#[cfg(feature = "init-info")]
+impl InitInfo {
+    fn current() -> Self {
+        let thread = thread::current();
+        Self {
+            thread_id: thread.id(),
+            thread_name: thread.name().map(ToString::to_string),
+            time: SystemTime::now(),
+        }
+    }
+
+    /// The id of the thread that initialized the container.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// The name of the thread that initialized the container, if it had
+    /// one.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// The time at which the container was initialized.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+}
+
+/// Storage for an optional [`InitInfo`].
+///
+/// Without the `init-info` feature, this is a zero-sized type and every
+/// method does nothing.
+pub(crate) struct InfoSlot {
+    /// A pointer created by `Box::into_raw`, or null.
+    #[cfg(feature = "init-info")]
+    ptr: AtomicPtr<InitInfo>,
+}
+
+impl InfoSlot {
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "init-info")]
+            ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Record that the current thread just initialized the container.
+    ///
+    /// This should only be called by the thread that won the race to
+    /// initialize the container.
+    #[inline]
+    pub(crate) fn record(&self) {
+        #[cfg(feature = "init-info")]
+        {
+            let info = Box::into_raw(Box::new(InitInfo::current()));
+            let old = self.ptr.swap(info, Ordering::AcqRel);
+            if !old.is_null() {
+                // This happens if the container was reset and initialized
+                // again.
+                //
+                // SAFETY: `old` was created by `Box::into_raw`. Resetting a
+                // container requires that no references into it exist.
+                let _owned = unsafe { Box::from_raw(old) };
+            }
+        }
+    }
+
+    /// Get the recorded info, if any.
+    #[cfg(feature = "init-info")]
+    pub(crate) fn get(&self) -> Option<&InitInfo> {
+        let info = self.ptr.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null, or was created by
+        // `Box::into_raw` and lives until `self` is dropped or reset.
+        unsafe { info.as_ref() }
+    }
+
+    /// Forget the recorded info without dropping it.
+    ///
+    /// This is safe to call even if references to the info exist.
+    #[cfg(all(feature = "std", unix))]
+    #[inline]
+    pub(crate) fn leak(&self) {
+        #[cfg(feature = "init-info")]
+        self.ptr.store(ptr::null_mut(), Ordering::Release);
+    }
+
+    /// Drop the recorded info.
+    ///
+    /// # Safety
+    ///
+    /// No references to the info may exist.
+    #[inline]
+    pub(crate) unsafe fn reset(&self) {
+        #[cfg(feature = "init-info")]
+        {
+            let info = self.ptr.swap(ptr::null_mut(), Ordering::AcqRel);
+            if !info.is_null() {
+                // SAFETY: `info` was created by `Box::into_raw`, and the
+                // caller guarantees that no references to it exist.
+                let _owned = Box::from_raw(info);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "init-info")]
+impl Drop for InfoSlot {
+    fn drop(&mut self) {
+        // SAFETY: we have exclusive access.
+        unsafe { self.reset() };
+    }
+}
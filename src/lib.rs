@@ -131,9 +131,140 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+/// Panic with a message that names a container's type parameter.
+///
+/// With the `static-panic-messages` feature, the type name is omitted, so
+/// that the panic message is a static string and no formatting code is
+/// needed.
+#[cfg(not(feature = "static-panic-messages"))]
+macro_rules! panic_with_type {
+    ($msg:literal, $t:ty) => {
+        panic!(concat!($msg, "<{}>"), ::core::any::type_name::<$t>())
+    };
+}
+
+#[cfg(feature = "static-panic-messages")]
+macro_rules! panic_with_type {
+    ($msg:literal, $t:ty) => {
+        panic!($msg)
+    };
+}
+
+/// Panic with a caller-supplied context, followed by a message that names
+/// a container's type parameter.
+///
+/// With the `static-panic-messages` feature, both the context and the type
+/// name are omitted, so that the panic message is a static string.
+#[cfg(not(feature = "static-panic-messages"))]
+macro_rules! panic_with_context {
+    ($context:expr, $msg:literal, $t:ty) => {
+        panic!(
+            concat!("{}: ", $msg, "<{}>"),
+            $context,
+            ::core::any::type_name::<$t>()
+        )
+    };
+}
+
+#[cfg(feature = "static-panic-messages")]
+macro_rules! panic_with_context {
+    ($context:expr, $msg:literal, $t:ty) => {{
+        let _ = $context;
+        panic!($msg)
+    }};
+}
+
+mod chaos;
+#[cfg(feature = "std")]
+mod countdown;
+mod error;
+#[cfg(feature = "exit-cleanup")]
+mod exit;
+#[cfg(all(feature = "std", unix))]
+mod fork;
+mod freezearc;
 mod freezebox;
+#[cfg(feature = "std")]
+mod indexmap;
+mod initinfo;
+#[cfg(feature = "std")]
+mod log;
 mod maybebox;
+#[cfg(feature = "std")]
+mod multimap;
+#[cfg(feature = "std")]
+mod mutexbox;
+mod observer;
+mod once;
+#[cfg(feature = "std")]
+mod onceevent;
+mod ownedref;
+#[cfg(feature = "std")]
+mod pool;
+mod race;
+mod redacted;
+#[cfg(feature = "std")]
+mod registry;
+mod slice;
+#[cfg(feature = "std")]
+mod snapshotbox;
+#[cfg(feature = "std")]
+mod sortedvec;
+#[cfg(feature = "std")]
+mod spawn;
+mod stackfreeze;
+mod teardownbox;
+#[cfg(feature = "std")]
+mod tlsbox;
+#[cfg(feature = "std")]
+mod waiter;
+mod weakbox;
 
-pub use self::freezebox::FreezeBox;
-pub use self::maybebox::MaybeBox;
+#[cfg(feature = "std")]
+pub use self::countdown::CountdownLatch;
+pub use self::error::{Error, InitError};
+#[cfg(feature = "exit-cleanup")]
+pub use self::exit::run_exit_cleanups;
+pub use self::freezearc::FreezeArc;
+pub use self::freezebox::{FreezeBox, FrozenRef};
+#[cfg(feature = "std")]
+pub use self::indexmap::{FrozenIndexMap, Keys};
+#[cfg(feature = "init-info")]
+pub use self::initinfo::InitInfo;
+#[cfg(feature = "std")]
+pub use self::log::{FrozenLog, LogCursor, NextEntry};
+pub use self::maybebox::{MaybeBox, Reader};
+#[cfg(feature = "std")]
+pub use self::multimap::FrozenMultiMap;
+#[cfg(feature = "std")]
+pub use self::mutexbox::MutexBox;
+#[cfg(feature = "alloc-observer")]
+pub use self::observer::{clear_alloc_observer, set_alloc_observer, AllocEvent, AllocKind};
+#[doc(hidden)]
+pub use self::once::OnceBlock;
+#[cfg(feature = "std")]
+pub use self::onceevent::OnceEvent;
+pub use self::ownedref::OwnedRef;
+#[cfg(feature = "std")]
+pub use self::pool::{LazyPool, PoolGuard};
+pub use self::race::{OnceBool, OnceNonZeroUsize, OnceRef};
+pub use self::redacted::Redacted;
+#[cfg(feature = "std")]
+pub use self::registry::NamedRegistry;
+pub use self::slice::InitSliceExt;
+#[cfg(feature = "std")]
+pub use self::snapshotbox::{Snapshot, SnapshotBox};
+#[cfg(feature = "std")]
+pub use self::sortedvec::FrozenSortedVec;
+pub use self::stackfreeze::StackFreeze;
+pub use self::teardownbox::{TeardownBox, TeardownGuard, TeardownKey};
+#[cfg(feature = "std")]
+pub use self::tlsbox::TlsBox;
+#[cfg(feature = "std")]
+pub use self::waiter::Wait;
+pub use self::weakbox::WeakBox;
+#[cfg(feature = "macros")]
+pub use freezebox_macros::late_init;
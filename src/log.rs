@@ -0,0 +1,430 @@
+//! This is the FrozenLog implementation.
+
+use crate::waiter::Waiters;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `FrozenLog` is an append-only sequence of entries.
+///
+/// Entries can be appended using only a shared reference, and references
+/// to appended entries remain valid for as long as the `FrozenLog` lives.
+/// Entries can never be modified or removed, except by consuming the log
+/// with `into_vec()`. Entries may be unsized (for example, `dyn Trait`) if
+/// they are appended with [`push_box`][FrozenLog::push_box].
+///
+/// Readers use a [`LogCursor`] to walk the log in order. A cursor can
+/// return the entries that are already present (it implements
+/// `Iterator`), block until the next entry is appended, or wait for it
+/// asynchronously.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::FrozenLog;
+/// use std::sync::Arc;
+///
+/// let log = Arc::new(FrozenLog::<String>::default());
+/// let log2 = log.clone();
+///
+/// let reader = std::thread::spawn(move || {
+///     let mut cursor = log2.cursor();
+///     let first = cursor.wait_next().clone();
+///     let second = cursor.wait_next().clone();
+///     (first, second)
+/// });
+///
+/// log.push("starting".to_string());
+/// log.push("ready".to_string());
+/// let (first, second) = reader.join().unwrap();
+/// assert_eq!((first.as_str(), second.as_str()), ("starting", "ready"));
+/// ```
+pub struct FrozenLog<T: ?Sized> {
+    /// Pointers created by `Box::into_raw`, in append order.
+    ///
+    /// Raw pointers are used instead of `Box`, for the same reason as in
+    /// `FrozenSortedVec`: moving a `Box` would invalidate the shared
+    /// references we have handed out.
+    entries: Mutex<Vec<*mut T>>,
+    /// The number of entries, readable without taking the lock.
+    len: AtomicUsize,
+    waiters: Waiters,
+    phantom: PhantomData<T>,
+}
+
+impl<T: ?Sized> FrozenLog<T> {
+    fn lock(&self) -> MutexGuard<'_, Vec<*mut T>> {
+        // The lock is never held while running user code, so poisoning
+        // can't leave the log inconsistent.
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a reference to an entry.
+    fn entry_ref(&self, ptr: *mut T) -> &T {
+        // SAFETY: every pointer in the log was created by
+        // `Box::into_raw`, and is only freed when `self` is consumed or
+        // dropped.
+        unsafe { &*ptr }
+    }
+
+    /// Append a boxed entry, waking any readers waiting for it.
+    ///
+    /// This allows unsized entries, such as trait objects, to be stored
+    /// without an extra layer of boxing.
+    ///
+    /// Returns the position of the new entry.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::FrozenLog;
+    /// use std::fmt::Display;
+    ///
+    /// let log = FrozenLog::<dyn Display>::default();
+    /// log.push_box(Box::new(42));
+    /// log.push_box(Box::new("hello"));
+    /// assert_eq!(log.get(1).unwrap().to_string(), "hello");
+    /// ```
+    pub fn push_box(&self, val: Box<T>) -> usize {
+        let ptr = Box::into_raw(val);
+        let index = {
+            let mut entries = self.lock();
+            entries.push(ptr);
+            // `Release` ordering ensures that readers who see the new
+            // length will also see the new entry.
+            self.len.store(entries.len(), Ordering::Release);
+            entries.len() - 1
+        };
+        self.waiters.notify_all();
+        index
+    }
+
+    /// Return the entry at a given position.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let entries = self.lock();
+        entries.get(index).map(|&e| self.entry_ref(e))
+    }
+
+    /// Return the number of entries.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Test whether the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Create a cursor that starts at the beginning of the log.
+    pub fn cursor(&self) -> LogCursor<'_, T> {
+        self.cursor_at(0)
+    }
+
+    /// Create a cursor that starts at a given position.
+    ///
+    /// Use `cursor_at(log.len())` to only see entries appended later.
+    pub fn cursor_at(&self, position: usize) -> LogCursor<'_, T> {
+        LogCursor {
+            log: self,
+            position,
+        }
+    }
+
+    /// Consume the log and return its boxed entries, in append order.
+    pub fn into_boxes(mut self) -> Vec<Box<T>> {
+        let entries = self
+            .entries
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Take the pointers, so that `Drop` doesn't free them again.
+        let entries = core::mem::take(entries);
+
+        // SAFETY: every pointer was created by `Box::into_raw`, and we
+        // have exclusive access.
+        entries
+            .into_iter()
+            .map(|e| unsafe { Box::from_raw(e) })
+            .collect()
+    }
+}
+
+impl<T> FrozenLog<T> {
+    /// Append an entry, waking any readers waiting for it.
+    ///
+    /// Returns the position of the new entry.
+    pub fn push(&self, val: T) -> usize {
+        self.push_box(Box::new(val))
+    }
+
+    /// Append every entry from an iterator, waking any readers waiting
+    /// for them.
+    ///
+    /// The entries are appended together, taking the lock and waking
+    /// readers only once, which is much faster than calling `push` for
+    /// each one. Readers never see a partial batch.
+    ///
+    /// Returns the positions of the new entries.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::FrozenLog;
+    ///
+    /// let log = FrozenLog::<u32>::default();
+    /// log.push(0);
+    /// assert_eq!(log.extend(1..1000), 1..1000);
+    /// assert_eq!(log.get(999), Some(&999));
+    /// ```
+    pub fn extend<I>(&self, iter: I) -> Range<usize>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Allocate the entries before taking the lock, because the
+        // iterator is user code.
+        let new_entries: Vec<Box<T>> = iter.into_iter().map(Box::new).collect();
+        let range = {
+            let mut entries = self.lock();
+            let start = entries.len();
+            entries.extend(new_entries.into_iter().map(Box::into_raw));
+            // `Release` ordering ensures that readers who see the new
+            // length will also see the new entries.
+            self.len.store(entries.len(), Ordering::Release);
+            start..entries.len()
+        };
+        self.waiters.notify_all();
+        range
+    }
+
+    /// Consume the log and return its entries, in append order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.into_boxes().into_iter().map(|e| *e).collect()
+    }
+}
+
+impl<T: Clone> FrozenLog<T> {
+    /// Append clones of every entry in a slice.
+    ///
+    /// This is the same as [`extend`][FrozenLog::extend], for a slice.
+    pub fn extend_from_slice(&self, vals: &[T]) -> Range<usize> {
+        self.extend(vals.iter().cloned())
+    }
+}
+
+impl<T: ?Sized> Default for FrozenLog<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+            len: AtomicUsize::new(0),
+            waiters: Waiters::default(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for FrozenLog<T> {
+    fn drop(&mut self) {
+        let entries = self
+            .entries
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for &e in entries.iter() {
+            // SAFETY: every pointer was created by `Box::into_raw`, and we
+            // have exclusive access.
+            let _owned = unsafe { Box::from_raw(e) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `FrozenLog` owns its entries, like a `Vec<Box<T>>`.
+unsafe impl<T: Send + ?Sized> Send for FrozenLog<T> {}
+
+// SAFETY: shared references to the entries are handed out to any thread,
+// and an entry appended by one thread may be dropped by another.
+unsafe impl<T: Send + Sync + ?Sized> Sync for FrozenLog<T> {}
+
+/// A reader's position in a [`FrozenLog`].
+///
+/// Created by [`FrozenLog::cursor`]. As an `Iterator`, a cursor returns
+/// the entries that are currently in the log, and returns `None` when it
+/// reaches the end. It can continue later, once more entries have been
+/// appended.
+pub struct LogCursor<'a, T: ?Sized> {
+    log: &'a FrozenLog<T>,
+    position: usize,
+}
+
+impl<'a, T: ?Sized> LogCursor<'a, T> {
+    /// Return the position of the next entry this cursor will read.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Read the next entry, blocking the current thread until it has been
+    /// appended.
+    pub fn wait_next(&mut self) -> &'a T {
+        let log = self.log;
+        let position = self.position;
+        log.waiters.wait(|| log.len() > position);
+        self.next().unwrap()
+    }
+
+    /// Read the next entry, waiting asynchronously until it has been
+    /// appended.
+    pub fn next_async(&mut self) -> NextEntry<'a, '_, T> {
+        NextEntry { cursor: self }
+    }
+}
+
+impl<'a, T: ?Sized> Iterator for LogCursor<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let entry = self.log.get(self.position)?;
+        self.position += 1;
+        Some(entry)
+    }
+}
+
+impl<T: ?Sized> Clone for LogCursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            log: self.log,
+            position: self.position,
+        }
+    }
+}
+
+/// A future that resolves to the next entry of a [`LogCursor`].
+///
+/// This is returned by [`LogCursor::next_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct NextEntry<'a, 'c, T: ?Sized> {
+    cursor: &'c mut LogCursor<'a, T>,
+}
+
+impl<'a, T: ?Sized> Future for NextEntry<'a, '_, T> {
+    type Output = &'a T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<&'a T> {
+        let log = self.cursor.log;
+        let position = self.cursor.position;
+        match log.waiters.poll(cx, || log.len() > position) {
+            Poll::Ready(()) => Poll::Ready(self.cursor.next().unwrap()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Must fail to compile because FrozenLog<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::FrozenLog;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = FrozenLog::<Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenLog;
+    use crate::waiter::block_on;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::thread;
+
+    #[test]
+    fn log_test() {
+        let x = FrozenLog::<String>::default();
+        assert!(x.is_empty());
+        let a = x.get(x.push("a".to_string())).unwrap();
+        let mut cursor = x.cursor();
+        x.push("b".to_string());
+
+        assert_eq!(cursor.next().unwrap(), "a");
+        assert_eq!(cursor.wait_next(), "b");
+        assert!(cursor.next().is_none());
+        assert_eq!(cursor.position(), 2);
+
+        x.push("c".to_string());
+        assert_eq!(cursor.next().unwrap(), "c");
+        assert_eq!(x.cursor_at(1).cloned().collect::<Vec<_>>(), ["b", "c"]);
+
+        // References survive later appends.
+        assert_eq!(a, "a");
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.into_vec(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn extend_test() {
+        let x = FrozenLog::<String>::default();
+        let mut cursor = x.cursor();
+        assert_eq!(
+            x.extend_from_slice(&["a".to_string(), "b".to_string()]),
+            0..2
+        );
+        assert_eq!(x.extend(None), 2..2);
+        assert_eq!(x.extend(Some("c".to_string())), 2..3);
+        assert_eq!(cursor.wait_next(), "a");
+        assert_eq!(cursor.collect::<Vec<_>>(), ["b", "c"]);
+    }
+
+    #[test]
+    fn unsized_test() {
+        let x = FrozenLog::<[u32]>::default();
+        x.push_box(Box::new([1, 2]));
+        x.push_box(Box::new([3]));
+        assert_eq!(x.cursor().map(<[u32]>::len).sum::<usize>(), 3);
+        let boxes = x.into_boxes();
+        assert_eq!(&*boxes[1], [3]);
+    }
+
+    #[test]
+    fn concurrent_test() {
+        let x = Arc::new(FrozenLog::<u32>::default());
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let x = x.clone();
+                thread::spawn(move || {
+                    let mut cursor = x.cursor();
+                    (0..100).map(|_| *cursor.wait_next()).sum::<u32>()
+                })
+            })
+            .collect();
+        for i in 0..100 {
+            x.push(i);
+        }
+        for r in readers {
+            assert_eq!(r.join().unwrap(), 4950);
+        }
+    }
+
+    #[test]
+    fn async_test() {
+        let x = Arc::new(FrozenLog::<u32>::default());
+        let x2 = x.clone();
+        let handle = thread::spawn(move || {
+            let mut cursor = x2.cursor();
+            let a = *block_on(cursor.next_async());
+            let b = *block_on(cursor.next_async());
+            (a, b)
+        });
+        thread::sleep(Duration::from_millis(10));
+        x.push(1);
+        x.push(2);
+        assert_eq!(handle.join().unwrap(), (1, 2));
+    }
+}
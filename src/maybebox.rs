@@ -1,10 +1,16 @@
 //! This is the MaybeBox implementation.
 
+use crate::initinfo::InfoSlot;
+#[cfg(feature = "init-info")]
+use crate::initinfo::InitInfo;
+use crate::{FreezeBox, InitError};
 use alloc::boxed::Box;
-use core::any::type_name;
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::Deref;
+use core::option;
 use core::ptr::null_mut;
+use core::str::FromStr;
 use core::sync::atomic::{AtomicPtr, Ordering};
 use core::{mem, ptr};
 
@@ -39,8 +45,12 @@ use core::{mem, ptr};
 /// }
 /// ```
 ///
+// `FreezeBox` and `MaybeBox` must have identical layouts, so that
+// `as_maybe` and `as_freeze` can convert references between them.
+#[repr(C)]
 pub struct MaybeBox<T> {
     inner: AtomicPtr<T>,
+    info: InfoSlot,
     phantom: PhantomData<T>,
 }
 
@@ -80,21 +90,40 @@ impl<T> MaybeBox<T> {
     pub const fn const_default() -> Self {
         Self {
             inner: AtomicPtr::new(null_mut()),
+            info: InfoSlot::new(),
             phantom: PhantomData,
         }
     }
 
     /// Initialize a `MaybeBox`.
     ///
-    /// The new value will be stored on the heap.
+    /// The new value will be stored on the heap. Returns a reference to
+    /// the stored value.
     ///
     /// # Panics
     ///
     /// `lazy_init` will panic if the `FreezeBox` is already initialized.
     /// If it panics, the input value will be dropped.
     ///
-    pub fn lazy_init(&self, val: T) {
-        let ptr = Box::into_raw(Box::new(val));
+    pub fn lazy_init(&self, val: T) -> &T {
+        self.lazy_init_box(Box::new(val))
+    }
+
+    /// Initialize a `MaybeBox` with a value that is already on the heap.
+    ///
+    /// This is the same as [`lazy_init`][MaybeBox::lazy_init], except that
+    /// the existing allocation is installed directly, instead of moving
+    /// the value into a new one.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_box` will panic if the `MaybeBox` is already initialized.
+    /// If it panics, the input value will be dropped.
+    pub fn lazy_init_box(&self, val: Box<T>) -> &T {
+        let ptr = Box::into_raw(val);
+        // Report the allocation once the container owns it, to balance the
+        // free reported when the container (or a failed init) drops it.
+        crate::observer::observe_alloc::<T>();
 
         // Attempt to atomically swap from nullptr to `ptr`.
         //
@@ -114,6 +143,7 @@ impl<T> MaybeBox<T> {
         // `AcqRel`.
         //
         // If this succeeds, the MaybeBox is now initialized.
+        crate::chaos::delay();
         if self
             .inner
             .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
@@ -131,11 +161,165 @@ impl<T> MaybeBox<T> {
             // ownership here and drop the result.
 
             let _val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
 
-            panic!(
-                "lazy_init on already-initialized MaybeBox<{}>",
-                type_name::<T>()
-            );
+            panic_with_type!("lazy_init on already-initialized MaybeBox", T);
+        }
+        self.info.record();
+        // SAFETY: we just published `ptr`, and the value can't be removed
+        // while `self` is shared.
+        unsafe { &*ptr }
+    }
+
+    /// Initialize a `MaybeBox`, if it is not already initialized.
+    ///
+    /// This is the same as [`lazy_init`][MaybeBox::lazy_init], except that if
+    /// the `MaybeBox` is already initialized, `val` is returned in the `Err`
+    /// variant instead of panicking. This is useful when several
+    /// subsystems may try to install the same singleton.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// static LOGGER: MaybeBox<String> = MaybeBox::const_default();
+    /// assert_eq!(LOGGER.try_lazy_init("first".to_string()).unwrap(), "first");
+    /// assert_eq!(LOGGER.try_lazy_init("second".to_string()), Err("second".to_string()));
+    /// assert_eq!(LOGGER.get().unwrap(), "first");
+    /// ```
+    pub fn try_lazy_init(&self, val: T) -> Result<&T, T> {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+
+        // The atomic ordering here is the same as `lazy_init`.
+        crate::chaos::delay();
+        if self
+            .inner
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // SAFETY: `ptr` was just created above using `Box::into_raw`,
+            // and because compare_exchange failed, we are still its unique
+            // owner.
+            let val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+            return Err(*val);
+        }
+        self.info.record();
+        // SAFETY: we just published `ptr`, and the value can't be removed
+        // while `self` is shared.
+        Ok(unsafe { &*ptr })
+    }
+
+    /// Initialize the `MaybeBox`, unless another value got there first.
+    ///
+    /// Returns a reference to whichever value was stored. If the `MaybeBox`
+    /// was already initialized, `val` is dropped (without panicking).
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let x = MaybeBox::<u32>::default();
+    /// assert_eq!(*x.race_init(1), 1);
+    /// assert_eq!(*x.race_init(2), 1);
+    /// ```
+    pub fn race_init(&self, val: T) -> &T {
+        match self.try_lazy_init(val) {
+            Ok(val) => val,
+            // Another thread won the race, so drop our value.
+            Err(_) => self.get().unwrap(),
+        }
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, initializing it with
+    /// `f` if necessary.
+    ///
+    /// If several threads race to initialize the `MaybeBox`, each may run its
+    /// own `f`, but only one value is stored. The other values are dropped
+    /// (without panicking), and every caller gets a reference to the
+    /// stored value.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let cache = MaybeBox::<Vec<u32>>::default();
+    /// assert_eq!(cache.get_or_init(|| vec![1, 2, 3]).len(), 3);
+    /// // Already initialized, so the closure doesn't run.
+    /// assert_eq!(cache.get_or_init(|| unreachable!()).len(), 3);
+    /// ```
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.get() {
+            Some(val) => val,
+            None => self.race_init(f()),
+        }
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, initializing it with
+    /// the result of `f` if necessary.
+    ///
+    /// If `f` returns an error, the `MaybeBox` is left uninitialized and the
+    /// error is returned, so the next call runs its initializer again. To
+    /// remember the error instead, use
+    /// [`get_or_try_init_cached`][Self::get_or_try_init_cached]. Races
+    /// between initializers are handled as in
+    /// [`get_or_init`][Self::get_or_init].
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let port = MaybeBox::<u16>::default();
+    /// assert!(port.get_or_try_init(|| "http".parse::<u16>()).is_err());
+    /// assert!(!port.is_initialized());
+    /// assert_eq!(port.get_or_try_init(|| "8080".parse::<u16>()), Ok(&8080));
+    /// ```
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+        Ok(self.race_init(f()?))
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, initializing it with
+    /// the result of `f` if necessary, and caching any error in `errors`.
+    ///
+    /// Unlike [`get_or_try_init`][Self::get_or_try_init], a failed
+    /// initialization is remembered: once `errors` holds an error, later
+    /// calls return it without running `f`. If several threads fail at once,
+    /// only the first error is stored, and every caller gets a reference to
+    /// it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let port = MaybeBox::<u16>::default();
+    /// let error = MaybeBox::default();
+    /// assert!(port.get_or_try_init_cached(&error, || "http".parse::<u16>()).is_err());
+    /// // The error is cached, so the closure doesn't run.
+    /// assert!(port.get_or_try_init_cached(&error, || unreachable!()).is_err());
+    /// assert!(!port.is_initialized());
+    /// ```
+    pub fn get_or_try_init_cached<'a, F, E>(
+        &'a self,
+        errors: &'a MaybeBox<E>,
+        f: F,
+    ) -> Result<&'a T, &'a E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(val) = self.get() {
+            return Ok(val);
+        }
+        if let Some(err) = errors.get() {
+            return Err(err);
+        }
+        match f() {
+            Ok(val) => Ok(self.race_init(val)),
+            Err(err) => Err(errors.race_init(err)),
         }
     }
 
@@ -148,29 +332,558 @@ impl<T> MaybeBox<T> {
         unsafe { ptr.as_ref() }
     }
 
+    /// Get a reference to the data in the `MaybeBox`, panicking if it is
+    /// uninitialized.
+    ///
+    /// This is useful when the `MaybeBox` is expected to be initialized,
+    /// and a missing value is a bug that deserves a clear message.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the message `msg` if the `MaybeBox` is uninitialized.
+    #[track_caller]
+    pub fn expect(&self, msg: &str) -> &T {
+        self.get().expect(msg)
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, or to `default` if
+    /// it is uninitialized.
+    ///
+    /// This is convenient for configuration values that have a static
+    /// default.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// static TIMEOUT_SECS: MaybeBox<u64> = MaybeBox::const_default();
+    /// assert_eq!(*TIMEOUT_SECS.get_or(&30), 30);
+    /// ```
+    pub fn get_or<'a>(&'a self, default: &'a T) -> &'a T {
+        self.get().unwrap_or(default)
+    }
+
+    /// Return an iterator over the value in the `MaybeBox`.
+    ///
+    /// The iterator yields one item if the `MaybeBox` is initialized, and
+    /// none otherwise. This is like `Option::iter`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let a = MaybeBox::new(Some(1));
+    /// let b = MaybeBox::<u32>::default();
+    /// let c = MaybeBox::new(Some(3));
+    /// let sum: u32 = a.iter().chain(&b).chain(&c).sum();
+    /// assert_eq!(sum, 4);
+    /// ```
+    pub fn iter(&self) -> option::IntoIter<&T> {
+        self.get().into_iter()
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, or an error.
+    ///
+    /// If the `MaybeBox` is initialized, this will return `Ok(&T)`;
+    /// otherwise it will return `Err(err)`. This is like
+    /// `Option::ok_or`, and makes it easy to use `?` on a `MaybeBox`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// fn name_len(name: &MaybeBox<String>) -> Result<usize, &'static str> {
+    ///     Ok(name.ok_or("no name")?.len())
+    /// }
+    ///
+    /// let x = MaybeBox::<String>::default();
+    /// assert_eq!(name_len(&x), Err("no name"));
+    /// x.lazy_init("hello".to_string());
+    /// assert_eq!(name_len(&x), Ok(5));
+    /// ```
+    pub fn ok_or<E>(&self, err: E) -> Result<&T, E> {
+        self.get().ok_or(err)
+    }
+
+    /// Get a reference to the data in the `MaybeBox`, or an error.
+    ///
+    /// This is the same as [`ok_or`][`MaybeBox::ok_or`], except that the
+    /// error is only computed if the `MaybeBox` is uninitialized.
+    pub fn ok_or_else<E, F>(&self, err: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> E,
+    {
+        self.get().ok_or_else(err)
+    }
+
+    /// Call a function with a reference to the data in the `MaybeBox`.
+    ///
+    /// If the `MaybeBox` is initialized, this returns `Some` with the
+    /// result of `f`; otherwise `f` is not called, and this returns `None`.
+    pub fn with<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get().map(f)
+    }
+
+    /// Initialize a `MaybeBox` without checking whether it is already initialized.
+    ///
+    /// This performs a plain atomic store rather than a compare-exchange,
+    /// which is slightly cheaper when initializing many containers during
+    /// a single-threaded startup phase.
+    ///
+    /// # Safety
+    ///
+    /// The `MaybeBox` must be uninitialized, and no other thread may be
+    /// initializing it at the same time.
+    pub unsafe fn init_unchecked(&self, val: T) {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+        debug_assert!(self.inner.load(Ordering::Relaxed).is_null());
+        self.inner.store(ptr, Ordering::Release);
+        self.info.record();
+    }
+
     /// Test whether a `MaybeBox` is initialized.
     pub fn is_initialized(&self) -> bool {
         let ptr = self.inner.load(Ordering::Acquire);
         !ptr.is_null()
     }
 
+    /// View this `MaybeBox` as a `FreezeBox`.
+    ///
+    /// This allows passing a `MaybeBox` to code that expects a
+    /// `FreezeBox`, without copying or consuming it. Both views share the
+    /// same contents; initializing either one initializes both.
+    ///
+    /// Like any `FreezeBox`, dereferencing the view will panic if the
+    /// `MaybeBox` is uninitialized.
+    pub fn as_freeze(&self) -> &FreezeBox<T> {
+        // SAFETY: `FreezeBox` and `MaybeBox` have identical layouts, and
+        // every state of one is a valid state of the other.
+        unsafe { &*(self as *const Self as *const FreezeBox<T>) }
+    }
+
+    /// Get details about the initialization of this `MaybeBox`.
+    ///
+    /// Returns the thread that called `lazy_init`, and when. This is
+    /// useful for debugging initialization-order bugs. The info may be
+    /// missing for a short time after another thread initializes the
+    /// `MaybeBox`.
+    ///
+    /// This method requires the `init-info` feature.
+    #[cfg(feature = "init-info")]
+    pub fn init_info(&self) -> Option<&InitInfo> {
+        self.info.get()
+    }
+
+    /// Assert that the `MaybeBox` is initialized.
+    ///
+    /// This is intended to be called at the end of a startup phase, so
+    /// that a missing initialization is reported where it can be
+    /// explained, rather than at some later use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `MaybeBox` is uninitialized. The panic message starts
+    /// with `context`, unless the `static-panic-messages` feature is
+    /// enabled.
+    ///
+    /// # Examples
+    /// ```should_panic
+    /// # use freezebox::MaybeBox;
+    /// let db_url = MaybeBox::<String>::default();
+    /// db_url.assert_initialized("startup incomplete: database URL not loaded");
+    /// ```
+    #[track_caller]
+    pub fn assert_initialized(&self, context: &str) {
+        if !self.is_initialized() {
+            panic_with_context!(context, "uninitialized MaybeBox", T);
+        }
+    }
+
+    /// Get a mutable reference to the data in the `MaybeBox`.
+    ///
+    /// Returns `None` if the `MaybeBox` is uninitialized. No atomic operations
+    /// are needed, because the mutable borrow guarantees exclusive access.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let mut x = MaybeBox::new(Some(vec![1, 2]));
+    /// x.get_mut().unwrap().push(3);
+    /// assert_eq!(x.into_inner(), Some(vec![1, 2, 3]));
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let ptr = *self.inner.get_mut();
+        // SAFETY: the pointer is either null, or was created by
+        // `Box::into_raw` and is owned by `self`. We have exclusive access,
+        // so no other references to the value exist.
+        unsafe { ptr.as_mut() }
+    }
+
+    /// Take the value out of the `MaybeBox`, leaving it uninitialized.
+    ///
+    /// Returns `None` if the `MaybeBox` was already uninitialized. The
+    /// `MaybeBox` can be initialized again afterwards, which is useful when
+    /// recycling a container that isn't shared.
+    ///
+    /// This is an associated function, like [`FreezeBox::take`], and is
+    /// called as `MaybeBox::take(&mut x)`.
+    pub fn take(this: &mut Self) -> Option<T> {
+        let ptr = mem::replace(this.inner.get_mut(), ptr::null_mut());
+        // SAFETY: we have exclusive access, so no references to the info
+        // exist.
+        unsafe { this.info.reset() };
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: `ptr` was created by `Box::into_raw` in `lazy_init`, and
+        // we just removed it from the container.
+        let owned = unsafe { Box::from_raw(ptr) };
+        crate::observer::observe_free::<T>();
+        Some(*owned)
+    }
+
+    /// Replace the value in the `MaybeBox`, returning the old value.
+    ///
+    /// Returns `None` if the `MaybeBox` was uninitialized. Because this
+    /// requires exclusive access, it doesn't conflict with the write-once
+    /// guarantee that shared references rely on.
+    ///
+    /// This is an associated function, like [`FreezeBox::replace`], and is
+    /// called as `MaybeBox::replace(&mut x, val)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let mut level = MaybeBox::<u8>::default();
+    /// assert_eq!(MaybeBox::replace(&mut level, 1), None);
+    /// assert_eq!(MaybeBox::replace(&mut level, 2), Some(1));
+    /// assert_eq!(level.get(), Some(&2));
+    /// ```
+    pub fn replace(this: &mut Self, val: T) -> Option<T> {
+        let old = Self::take(this);
+        this.lazy_init(val);
+        old
+    }
+
     /// Consume the `MaybeBox` and return its contents.
     pub fn into_inner(self) -> Option<T> {
-        let ptr = self.inner.load(Ordering::Acquire);
+        let tmp_box = Self::into_box(self)?;
+        crate::observer::observe_free::<T>();
+        Some(*tmp_box)
+    }
+
+    /// Consume the `MaybeBox` and return its contents, without moving them
+    /// out of their heap allocation.
+    ///
+    /// This avoids copying large values. The allocation is handed to the
+    /// caller rather than freed, so it isn't reported as a free to the
+    /// alloc observer.
+    ///
+    /// This is an associated function, like [`FreezeBox::into_box`], and is
+    /// called as `MaybeBox::into_box(x)`.
+    pub fn into_box(this: Self) -> Option<Box<T>> {
+        let ptr = this.inner.load(Ordering::Acquire);
+        // SAFETY: we own `this`, so no references to the info exist.
+        unsafe { this.info.reset() };
         // Prevent Drop::drop() from being called on the MaybeBox
         // because we are transferring ownership elsewhere.
-        mem::forget(self);
+        mem::forget(this);
         if ptr.is_null() {
             return None;
         }
 
-        // SAFETY: because we are consuming self, we must have sole ownership
+        // SAFETY: because we are consuming `this`, we must have sole ownership
         // of the MaybeBox contents. `lazy_init` created `ptr` from an
-        // owning `Box<T>`, so it's safe for us to recreate that Box and drop
-        // it.
+        // owning `Box<T>`, so it's safe for us to recreate that Box and hand
+        // it to the caller.
+        Some(unsafe { Box::from_raw(ptr) })
+    }
 
-        let tmp_box = unsafe { Box::from_raw(ptr) };
-        Some(*tmp_box)
+    /// Consume the `MaybeBox` and leak its contents, returning a mutable
+    /// reference that lives for the rest of the program.
+    ///
+    /// Like [`Box::leak`], this is intended for values that are never
+    /// dropped, such as singletons built during startup. Returns `None` if
+    /// the `MaybeBox` is uninitialized.
+    ///
+    /// This is an associated function, like [`FreezeBox::leak`], and is
+    /// called as `MaybeBox::leak(x)`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let config = MaybeBox::<String>::default();
+    /// config.lazy_init("verbose".to_string());
+    /// let config: &'static str = MaybeBox::leak(config).unwrap();
+    /// assert_eq!(config, "verbose");
+    /// ```
+    pub fn leak<'a>(this: Self) -> Option<&'a mut T>
+    where
+        T: 'a,
+    {
+        Self::into_box(this).map(Box::leak)
+    }
+
+    /// Consume the `MaybeBox` and return its contents, or return the
+    /// `MaybeBox` itself if it is uninitialized.
+    ///
+    /// This is like `Arc::try_unwrap`: an empty container is handed back
+    /// to the caller rather than being dropped.
+    pub fn try_into_inner(mut self) -> Result<T, Self> {
+        if self.inner.get_mut().is_null() {
+            return Err(self);
+        }
+        Ok(self.into_inner().unwrap())
+    }
+
+    /// Consume the `MaybeBox` and return its contents, or `default` if it is
+    /// uninitialized.
+    pub fn into_inner_or(self, default: T) -> T {
+        self.into_inner().unwrap_or(default)
+    }
+
+    /// Consume the `MaybeBox` and return its contents, or the result of
+    /// calling `f` if it is uninitialized.
+    pub fn into_inner_or_else<F>(self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.into_inner().unwrap_or_else(f)
+    }
+}
+
+impl<T> MaybeBox<T> {
+    /// Get a read-only view of the `MaybeBox`.
+    ///
+    /// A [`Reader`] can look at the value, but can't initialize it. Handing
+    /// out a `Reader` lets a component share read access while keeping the
+    /// exclusive right to initialize the `MaybeBox`.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::{MaybeBox, Reader};
+    ///
+    /// fn consumer(config: Reader<'_, String>) -> usize {
+    ///     config.get().map_or(0, |s| s.len())
+    /// }
+    ///
+    /// let config = MaybeBox::<String>::default();
+    /// assert!(config.reader().get().is_none());
+    /// config.lazy_init("verbose".to_string());
+    /// assert_eq!(consumer(config.reader()), 7);
+    /// ```
+    pub fn reader(&self) -> Reader<'_, T> {
+        Reader { target: self }
+    }
+}
+
+/// A read-only view of a [`MaybeBox`].
+///
+/// Created by [`MaybeBox::reader`]. A `Reader` can't be used to initialize
+/// the `MaybeBox`.
+///
+/// A `Reader` can't block until the `MaybeBox` is initialized, because a
+/// `MaybeBox` has no list of waiting threads to wake. To wait for
+/// initialization, pair the `MaybeBox` with a `OnceEvent` that is set
+/// after `lazy_init`.
+pub struct Reader<'a, T> {
+    target: &'a MaybeBox<T>,
+}
+
+impl<'a, T> Reader<'a, T> {
+    /// Try to get a reference to the value.
+    ///
+    /// Returns `None` if the `MaybeBox` is uninitialized.
+    pub fn get(&self) -> Option<&'a T> {
+        self.target.get()
+    }
+
+    /// Test whether the `MaybeBox` is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.target.is_initialized()
+    }
+}
+
+impl<T> Clone for Reader<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Reader<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Reader<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(val) => f.debug_tuple("Reader").field(val).finish(),
+            None => f.write_str("Reader(<uninitialized>)"),
+        }
+    }
+}
+
+#[cfg(feature = "test-support")]
+impl<T> MaybeBox<T> {
+    /// Reset a `MaybeBox` to the uninitialized state, dropping its contents.
+    ///
+    /// This is intended for tests that share a `static` `MaybeBox`, so that
+    /// each test can start with an uninitialized container. It should
+    /// never be used outside of tests.
+    ///
+    /// This method requires the `test-support` feature.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no references to the contents exist
+    /// (in any thread), and that no other thread is accessing the `MaybeBox`
+    /// while it is being reset.
+    pub unsafe fn reset_for_testing(&self) {
+        self.reset();
+    }
+}
+
+#[cfg(any(feature = "test-support", feature = "exit-cleanup"))]
+impl<T> MaybeBox<T> {
+    /// Reset to the uninitialized state, dropping the contents.
+    ///
+    /// # Safety
+    ///
+    /// No references to the contents may exist, and no other thread may
+    /// be accessing the `MaybeBox`.
+    unsafe fn reset(&self) {
+        self.info.reset();
+        let ptr = self.inner.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !ptr.is_null() {
+            // SAFETY: `ptr` was created by `Box::into_raw` in `lazy_init`,
+            // and we just removed it from the container. The caller
+            // guarantees that no references to the contents are alive.
+            let _owned = Box::<T>::from_raw(ptr);
+            crate::observer::observe_free::<T>();
+            // _owned will drop here.
+        }
+    }
+}
+
+#[cfg(feature = "exit-cleanup")]
+impl<T: Send + Sync + 'static> MaybeBox<T> {
+    /// Drop the contents of this `MaybeBox` when the process exits.
+    ///
+    /// The contents of a `static` are normally never dropped, so values
+    /// like files and sockets are never flushed or closed. Registered
+    /// containers are reset by [`run_exit_cleanups`], in reverse order of
+    /// registration. On unix platforms, `run_exit_cleanups` is also
+    /// called automatically when the process exits normally (via
+    /// `atexit`).
+    ///
+    /// This method requires the `exit-cleanup` feature.
+    ///
+    /// [`run_exit_cleanups`]: crate::run_exit_cleanups
+    ///
+    /// # Safety
+    ///
+    /// The contents must not be used after the process starts exiting:
+    /// no other thread may still be using them, and they must not be
+    /// accessed by destructors or other exit handlers that run later.
+    pub unsafe fn register_exit_cleanup(&'static self) {
+        crate::exit::register(self);
+    }
+}
+
+#[cfg(feature = "exit-cleanup")]
+impl<T: Send + Sync> crate::exit::ExitCleanup for MaybeBox<T> {
+    unsafe fn exit_cleanup(&self) {
+        self.reset();
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl<T: Send + Sync + 'static> MaybeBox<T> {
+    /// Make this `MaybeBox` uninitialized in the child process after `fork()`.
+    ///
+    /// Values like thread pools and sockets are often unusable in a forked
+    /// child. Registering a `MaybeBox` with `clear_on_fork` returns it to the
+    /// uninitialized state in the child, so that it can be initialized
+    /// again.
+    ///
+    /// The old value is leaked rather than dropped, because its destructor
+    /// may not be safe to run in the child, and because references to it
+    /// may still exist.
+    ///
+    /// This method requires the `std` feature, and is only available on
+    /// unix platforms.
+    pub fn clear_on_fork(&'static self) {
+        crate::fork::register(self);
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl<T: Send + Sync> crate::fork::ForkClear for MaybeBox<T> {
+    fn fork_clear(&self) {
+        // The old value is leaked, so any existing references to it
+        // remain valid.
+        self.inner.store(ptr::null_mut(), Ordering::Release);
+        self.info.leak();
+    }
+}
+
+impl<T: PartialEq> MaybeBox<T> {
+    /// Initialize a `MaybeBox`, tolerating re-initialization with an equal value.
+    ///
+    /// This behaves like [`lazy_init`][`MaybeBox::lazy_init`], except that if the
+    /// `MaybeBox` is already initialized with a value equal to `val`, then `val`
+    /// is dropped and the call succeeds. This is useful when multiple code
+    /// paths may legitimately compute the same value.
+    ///
+    /// Returns a reference to the stored value, which is either `val` or the
+    /// equal value that was already there.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let x = MaybeBox::<String>::default();
+    /// assert_eq!(x.lazy_init_idempotent("hello".to_string()), "hello");
+    /// assert_eq!(x.lazy_init_idempotent("hello".to_string()), "hello");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_idempotent` will panic if the `MaybeBox` is already
+    /// initialized with a different value. If it panics, the input value will
+    /// be dropped.
+    pub fn lazy_init_idempotent(&self, val: T) -> &T {
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+
+        // The atomic ordering here is the same as `lazy_init`.
+        crate::chaos::delay();
+        if let Err(existing) =
+            self.inner
+                .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+        {
+            // SAFETY: `ptr` was just created above using `Box::into_raw`,
+            // and because compare_exchange failed, we are still its unique
+            // owner.
+            let val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+
+            // SAFETY: compare_exchange failed, so `existing` is a non-null
+            // pointer that was published by a successful initialization. It
+            // remains valid for as long as `self` lives.
+            let existing = unsafe { &*existing };
+
+            if *existing != *val {
+                panic_with_type!(
+                    "lazy_init_idempotent with a different value on already-initialized MaybeBox",
+                    T
+                );
+            }
+            existing
+        } else {
+            self.info.record();
+            // SAFETY: we just published `ptr`, and the value can't be removed
+            // while `self` is shared.
+            unsafe { &*ptr }
+        }
     }
 }
 
@@ -191,10 +904,170 @@ impl<T: Deref> MaybeBox<T> {
     }
 }
 
+impl<T> MaybeBox<T> {
+    /// Initialize a `MaybeBox`, if the value passes a validation check.
+    ///
+    /// `check` is called on `val` before it is published. If `check`
+    /// returns an error, the `MaybeBox` is unchanged, and the error is
+    /// returned along with `val`. This ensures that readers can never
+    /// observe a value that violates an invariant.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_validated` will panic if the check passes but the
+    /// `MaybeBox` is already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let limit = MaybeBox::<u32>::default();
+    /// let nonzero = |x: &u32| if *x == 0 { Err("zero") } else { Ok(()) };
+    ///
+    /// let err = limit.lazy_init_validated(0, nonzero).unwrap_err();
+    /// assert_eq!(*err.error(), "zero");
+    /// assert!(!limit.is_initialized());
+    ///
+    /// assert_eq!(limit.lazy_init_validated(100, nonzero), Ok(&100));
+    /// assert_eq!(limit.get(), Some(&100));
+    /// ```
+    pub fn lazy_init_validated<E, F>(&self, val: T, check: F) -> Result<&T, InitError<E, T>>
+    where
+        F: FnOnce(&T) -> Result<(), E>,
+    {
+        if let Err(e) = check(&val) {
+            return Err(InitError::new(e, val));
+        }
+        Ok(self.lazy_init(val))
+    }
+}
+
+impl<T: Copy> MaybeBox<T> {
+    /// Get a copy of the value in the `MaybeBox`.
+    ///
+    /// Returns `None` if the `MaybeBox` is uninitialized. This is convenient
+    /// for small values like integers, where a reference isn't needed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// static PORT: MaybeBox<u16> = MaybeBox::const_default();
+    /// assert_eq!(PORT.get_copy(), None);
+    /// PORT.lazy_init(8080);
+    /// assert_eq!(PORT.get_copy(), Some(8080));
+    /// ```
+    pub fn get_copy(&self) -> Option<T> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or points to a value that
+        // lives as long as `self`.
+        unsafe { ptr.as_ref() }.copied()
+    }
+
+    /// Get a copy of the value in the `MaybeBox`.
+    ///
+    /// This is the same as [`get_copy`][MaybeBox::get_copy], named to match
+    /// `Option::copied`.
+    pub fn get_copied(&self) -> Option<T> {
+        self.get_copy()
+    }
+}
+
+impl<T: Clone> MaybeBox<T> {
+    /// Get a clone of the value in the `MaybeBox`.
+    ///
+    /// Returns `None` if the `MaybeBox` is uninitialized. This is the same as
+    /// `get().cloned()`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let name = MaybeBox::<String>::default();
+    /// assert_eq!(name.get_cloned(), None);
+    /// name.lazy_init("hello".to_string());
+    /// assert_eq!(name.get_cloned(), Some("hello".to_string()));
+    /// ```
+    pub fn get_cloned(&self) -> Option<T> {
+        self.get().cloned()
+    }
+
+    /// Initialize a `MaybeBox` with a clone of `val`.
+    ///
+    /// This is useful when the caller only has a reference to the value.
+    /// Unlike `lazy_init(val.clone())`, the clone is skipped if the
+    /// `MaybeBox` is already initialized.
+    /// Returns a reference to the stored value.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_cloned` will panic if the `MaybeBox` is already
+    /// initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let default_name = "default".to_string();
+    /// let slot = MaybeBox::<String>::default();
+    /// slot.lazy_init_cloned(&default_name);
+    /// assert_eq!(slot.get().unwrap(), "default");
+    /// ```
+    pub fn lazy_init_cloned(&self, val: &T) -> &T {
+        if self.is_initialized() {
+            panic_with_type!("lazy_init on already-initialized MaybeBox", T);
+        }
+        self.lazy_init(val.clone())
+    }
+}
+
+impl<T: FromStr> MaybeBox<T> {
+    /// Initialize a `MaybeBox` by parsing a string.
+    ///
+    /// This is a shortcut for parsing `s` and passing the result to
+    /// `lazy_init`, which is handy for configuration values that come from
+    /// the environment or the command line. If parsing fails, the error is
+    /// returned and the `MaybeBox` is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init_parse` will panic if parsing succeeds but the `MaybeBox`
+    /// is already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::MaybeBox;
+    /// let port = MaybeBox::<u16>::default();
+    /// assert!(port.lazy_init_parse("eighty").is_err());
+    /// assert_eq!(port.lazy_init_parse("80"), Ok(&80));
+    /// ```
+    pub fn lazy_init_parse(&self, s: &str) -> Result<&T, T::Err> {
+        Ok(self.lazy_init(s.parse()?))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MaybeBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or points to a value that
+        // lives as long as `self`; see `get`.
+        match unsafe { ptr.as_ref() } {
+            Some(val) => f.debug_tuple("MaybeBox").field(val).finish(),
+            None => f.write_str("MaybeBox(<uninitialized>)"),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MaybeBox<T> {
+    type Item = &'a T;
+    type IntoIter = option::IntoIter<&'a T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl<T> Default for MaybeBox<T> {
     fn default() -> Self {
         Self {
             inner: AtomicPtr::default(),
+            info: InfoSlot::new(),
             phantom: PhantomData,
         }
     }
@@ -216,6 +1089,7 @@ impl<T> Drop for MaybeBox<T> {
             // time that pointer will be seen; so there's no point.
 
             let _owned = unsafe { Box::<T>::from_raw(*inner) };
+            crate::observer::observe_free::<T>();
             // _owned will drop here.
         }
     }
@@ -247,9 +1121,12 @@ struct _Unused; // Only exists to get the compile-fail doctest
 #[cfg(test)]
 mod tests {
     use super::MaybeBox;
+    use alloc::boxed::Box;
+    use alloc::format;
     use alloc::string::String;
     use alloc::string::ToString;
     use alloc::sync::Arc;
+    use alloc::vec::Vec;
 
     #[test]
     fn freezebox_test() {
@@ -288,10 +1165,395 @@ mod tests {
         assert_eq!(y2, None);
     }
 
+    #[test]
+    fn try_into_inner_test() {
+        let x = MaybeBox::<String>::default();
+        let y = x.try_into_inner().unwrap_err();
+        y.lazy_init("hello".to_string());
+        assert_eq!(y.get().unwrap(), "hello");
+        assert_eq!(y.try_into_inner().ok().unwrap(), "hello");
+    }
+
+    #[test]
+    fn into_inner_or_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.into_inner_or("default".to_string()), "default");
+        let x = MaybeBox::new(Some("hello".to_string()));
+        assert_eq!(x.into_inner_or("default".to_string()), "hello");
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.into_inner_or_else(String::new), "");
+        let x = MaybeBox::new(Some("hello".to_string()));
+        assert_eq!(x.into_inner_or_else(|| unreachable!()), "hello");
+    }
+
+    #[test]
+    fn init_unchecked_test() {
+        static X: MaybeBox<String> = MaybeBox::const_default();
+        // SAFETY: X is uninitialized, and no other thread can see it.
+        unsafe { X.init_unchecked("hello".to_string()) };
+        assert_eq!(*X.get().unwrap(), "hello");
+    }
+
+    #[test]
+    fn with_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.with(|_| unreachable!()), None::<usize>);
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.with(|s| s.len()), Some(5));
+    }
+
+    #[test]
+    fn lazy_init_parse_test() {
+        let x = MaybeBox::<u32>::default();
+        assert!(x.lazy_init_parse("-1").is_err());
+        assert!(!x.is_initialized());
+        assert_eq!(x.lazy_init_parse("42"), Ok(&42));
+        assert_eq!(*x.get().unwrap(), 42);
+    }
+
+    #[test]
+    fn lazy_init_validated_test() {
+        let x = MaybeBox::<String>::default();
+        let not_empty = |s: &String| if s.is_empty() { Err("empty") } else { Ok(()) };
+        let err = x.lazy_init_validated(String::new(), not_empty).unwrap_err();
+        assert_eq!(err.into_parts(), ("empty", String::new()));
+        assert!(!x.is_initialized());
+        assert_eq!(
+            x.lazy_init_validated("hello".to_string(), not_empty)
+                .unwrap(),
+            "hello"
+        );
+        assert_eq!(*x.get().unwrap(), "hello");
+    }
+
+    #[test]
+    fn try_lazy_init_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.try_lazy_init("hello".to_string()).unwrap(), "hello");
+        assert_eq!(
+            x.try_lazy_init("goodbye".to_string()),
+            Err("goodbye".to_string())
+        );
+        assert_eq!(*x.get().unwrap(), "hello");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn get_or_init_test() {
+        let x = Arc::new(MaybeBox::<usize>::default());
+        let threads: Vec<_> = (0..8)
+            .map(|n| {
+                let x = x.clone();
+                std::thread::spawn(move || *x.get_or_init(|| n))
+            })
+            .collect();
+        let results: Vec<usize> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        let winner = *x.get_or_init(|| unreachable!());
+        assert!(results.iter().all(|&n| n == winner));
+    }
+
+    #[test]
+    fn get_mut_test() {
+        let mut x = MaybeBox::<String>::default();
+        assert!(x.get_mut().is_none());
+        x.lazy_init("hello".to_string());
+        x.get_mut().unwrap().push_str(" world");
+        assert_eq!(x.get().unwrap(), "hello world");
+    }
+
+    #[test]
+    fn take_test() {
+        let mut x = MaybeBox::<String>::default();
+        assert_eq!(MaybeBox::take(&mut x), None);
+        x.lazy_init("first".to_string());
+        assert_eq!(MaybeBox::take(&mut x).unwrap(), "first");
+        assert!(!x.is_initialized());
+        x.lazy_init("second".to_string());
+        assert_eq!(x.get().unwrap(), "second");
+    }
+
+    #[test]
+    fn replace_test() {
+        let value = Arc::new(());
+        let mut x = MaybeBox::<Arc<()>>::default();
+        assert!(MaybeBox::replace(&mut x, value.clone()).is_none());
+        assert_eq!(Arc::strong_count(&value), 2);
+        assert!(MaybeBox::replace(&mut x, value.clone()).is_some());
+        assert_eq!(Arc::strong_count(&value), 2);
+    }
+
+    #[test]
+    fn into_box_test() {
+        let x = MaybeBox::<[u8; 4]>::default();
+        assert!(MaybeBox::into_box(x).is_none());
+        let x = MaybeBox::new(Some([1, 2, 3, 4]));
+        assert_eq!(MaybeBox::into_box(x), Some(Box::new([1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn lazy_init_box_test() {
+        let x = MaybeBox::<String>::default();
+        let boxed = Box::new("hello".to_string());
+        let addr: *const String = &*boxed;
+        assert!(core::ptr::eq(x.lazy_init_box(boxed), addr));
+        assert_eq!(x.get().unwrap(), "hello");
+    }
+
+    #[test]
+    fn leak_test() {
+        assert!(MaybeBox::leak(MaybeBox::<u32>::default()).is_none());
+        let x: &'static mut u32 = MaybeBox::leak(MaybeBox::new(Some(1))).unwrap();
+        *x += 1;
+        assert_eq!(*x, 2);
+    }
+
+    #[test]
+    fn get_or_test() {
+        let x = MaybeBox::<u32>::default();
+        assert_eq!(*x.get_or(&7), 7);
+        x.lazy_init(1);
+        assert_eq!(*x.get_or(&7), 1);
+    }
+
+    #[test]
+    fn iter_test() {
+        let x = MaybeBox::<u32>::default();
+        assert_eq!(x.iter().count(), 0);
+        x.lazy_init(5);
+        let mut items = Vec::new();
+        for item in &x {
+            items.push(*item);
+        }
+        assert_eq!(items, [5]);
+    }
+
+    #[test]
+    fn lazy_init_ref_test() {
+        let x = MaybeBox::<String>::default();
+        let r = x.lazy_init("hello".to_string());
+        assert_eq!(r, "hello");
+        assert!(core::ptr::eq(r, x.get().unwrap()));
+    }
+
+    #[test]
+    fn race_init_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.race_init("first".to_string()), "first");
+        assert_eq!(x.race_init("second".to_string()), "first");
+    }
+
+    #[test]
+    fn get_or_try_init_test() {
+        let x = MaybeBox::<u32>::default();
+        assert_eq!(x.get_or_try_init(|| Err("nope")), Err("nope"));
+        assert!(!x.is_initialized());
+        assert_eq!(x.get_or_try_init(|| Ok::<_, ()>(1)), Ok(&1));
+        assert_eq!(x.get_or_try_init(|| Err(())), Ok(&1));
+    }
+
+    #[test]
+    fn get_or_try_init_retries_test() {
+        let x = MaybeBox::<u32>::default();
+        let mut calls = 0;
+        for _ in 0..3 {
+            let r = x.get_or_try_init(|| {
+                calls += 1;
+                Err(calls)
+            });
+            assert_eq!(r, Err(calls));
+        }
+        assert_eq!(calls, 3);
+        assert!(!x.is_initialized());
+    }
+
+    #[test]
+    fn get_or_try_init_cached_test() {
+        let x = MaybeBox::<u32>::default();
+        let errors = MaybeBox::default();
+        assert_eq!(
+            x.get_or_try_init_cached(&errors, || Err("nope")),
+            Err(&"nope")
+        );
+        assert_eq!(x.get_or_try_init_cached(&errors, || Ok(1)), Err(&"nope"));
+        assert!(!x.is_initialized());
+
+        let y = MaybeBox::<u32>::default();
+        let errors = MaybeBox::<&str>::default();
+        assert_eq!(y.get_or_try_init_cached(&errors, || Ok(1)), Ok(&1));
+        assert_eq!(y.get_or_try_init_cached(&errors, || Err("nope")), Ok(&1));
+        assert!(!errors.is_initialized());
+    }
+
+    #[test]
+    fn get_copy_test() {
+        let x = MaybeBox::<u32>::default();
+        assert_eq!(x.get_copy(), None);
+        x.lazy_init(5);
+        assert_eq!(x.get_copy(), Some(5));
+        assert_eq!(x.get_copied(), Some(5));
+    }
+
+    #[test]
+    fn get_cloned_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.get_cloned(), None);
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.get_cloned().unwrap(), "hello");
+    }
+
+    #[test]
+    fn lazy_init_cloned_test() {
+        let x = MaybeBox::<String>::default();
+        let val = "hello".to_string();
+        x.lazy_init_cloned(&val);
+        assert_eq!(*x.get().unwrap(), val);
+    }
+
+    #[test]
+    #[should_panic(expected = "lazy_init on already-initialized MaybeBox")]
+    fn lazy_init_cloned_initialized_test() {
+        struct NoClone;
+        impl Clone for NoClone {
+            fn clone(&self) -> Self {
+                unreachable!()
+            }
+        }
+        let x = MaybeBox::new(Some(NoClone));
+        x.lazy_init_cloned(&NoClone);
+    }
+
+    #[test]
+    fn reader_test() {
+        let x = MaybeBox::<String>::default();
+        let reader = x.reader();
+        assert!(!reader.is_initialized());
+        assert_eq!(format!("{:?}", reader), "Reader(<uninitialized>)");
+        x.lazy_init("hello".to_string());
+        assert_eq!(reader.get().unwrap(), "hello");
+        assert_eq!(format!("{:?}", reader), "Reader(\"hello\")");
+    }
+
+    #[test]
+    fn assert_initialized_test() {
+        let x = MaybeBox::new(Some("hello".to_string()));
+        x.assert_initialized("config phase");
+    }
+
+    #[cfg(not(feature = "static-panic-messages"))]
+    #[test]
+    #[should_panic(expected = "config phase: uninitialized MaybeBox")]
+    fn panic_assert_initialized() {
+        let x = MaybeBox::<String>::default();
+        x.assert_initialized("config phase");
+    }
+
+    #[cfg(all(feature = "std", feature = "static-panic-messages"))]
+    #[test]
+    fn static_panic_assert_initialized() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let x = MaybeBox::<String>::default();
+        let err = catch_unwind(AssertUnwindSafe(|| x.assert_initialized("config phase")));
+        let payload = err.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<&'static str>(),
+            Some(&"uninitialized MaybeBox")
+        );
+    }
+
+    #[test]
+    fn as_freeze_test() {
+        let x = MaybeBox::<String>::default();
+        assert!(!x.as_freeze().is_initialized());
+        x.as_freeze().lazy_init("hello".to_string());
+        assert_eq!(x.get().unwrap(), "hello");
+        assert_eq!(**x.as_freeze(), "hello");
+    }
+
+    #[cfg(feature = "init-info")]
+    #[test]
+    fn init_info_test() {
+        let x = MaybeBox::<String>::default();
+        assert!(x.init_info().is_none());
+        let handle = std::thread::Builder::new()
+            .name("initializer".to_string())
+            .spawn(move || {
+                x.lazy_init("hello".to_string());
+                x
+            })
+            .unwrap();
+        let initializer = handle.thread().id();
+        let x = handle.join().unwrap();
+        let info = x.init_info().unwrap();
+        assert_eq!(info.thread_id(), initializer);
+        assert_eq!(info.thread_name(), Some("initializer"));
+    }
+
     #[test]
     fn const_test() {
         static X: MaybeBox<String> = MaybeBox::const_default();
         X.lazy_init("hello".to_string());
         assert_eq!(X.get().unwrap(), "hello");
     }
+
+    #[test]
+    fn idempotent_init() {
+        let x = MaybeBox::<String>::default();
+        let first = x.lazy_init_idempotent("hello".to_string());
+        let second = x.lazy_init_idempotent("hello".to_string());
+        assert!(core::ptr::eq(first, second));
+        assert_eq!(x.get().unwrap(), "hello");
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_idempotent_mismatch() {
+        let x = MaybeBox::<String>::default();
+        x.lazy_init_idempotent("first".to_string());
+        x.lazy_init_idempotent("second".to_string());
+    }
+
+    #[test]
+    fn debug_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(format!("{:?}", x), "MaybeBox(<uninitialized>)");
+        x.lazy_init("hello".to_string());
+        assert_eq!(format!("{:?}", x), "MaybeBox(\"hello\")");
+    }
+
+    #[test]
+    fn ok_or_test() {
+        let x = MaybeBox::<String>::default();
+        assert_eq!(x.ok_or(1), Err(1));
+        assert_eq!(x.ok_or_else(|| 2), Err(2));
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.ok_or(1).unwrap(), "hello");
+        assert_eq!(x.ok_or_else(|| -> u32 { unreachable!() }).unwrap(), "hello");
+    }
+
+    #[test]
+    fn expect_test() {
+        let x = MaybeBox::<String>::default();
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.expect("x is set"), "hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "x is missing")]
+    fn panic_expect() {
+        let x = MaybeBox::<String>::default();
+        x.expect("x is missing");
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn reset_test() {
+        static X: MaybeBox<String> = MaybeBox::const_default();
+        X.lazy_init("first".to_string());
+        // SAFETY: no references to the contents exist.
+        unsafe { X.reset_for_testing() };
+        assert!(!X.is_initialized());
+        X.lazy_init("second".to_string());
+        assert_eq!(*X.get().unwrap(), "second");
+    }
 }
@@ -0,0 +1,244 @@
+//! This is the FrozenMultiMap implementation.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `FrozenMultiMap` is a map from keys to append-only lists of values.
+///
+/// Values can be appended using only a shared reference, and references
+/// to appended values remain valid for as long as the `FrozenMultiMap`
+/// lives. Values can never be removed, except by consuming the map with
+/// `into_map()`.
+///
+/// This is useful for registries like event listeners or tag indexes,
+/// which would otherwise need a `Mutex<HashMap<K, Vec<V>>>` and a lock
+/// held for as long as the values are in use.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::FrozenMultiMap;
+///
+/// let listeners = FrozenMultiMap::<&str, String>::default();
+/// let first: &String = listeners.append("click", "first".to_string());
+/// listeners.append("click", "second".to_string());
+/// listeners.append("hover", "third".to_string());
+///
+/// assert_eq!(first, "first");
+/// assert_eq!(listeners.get_all("click"), ["first", "second"]);
+/// assert_eq!(listeners.count("hover"), 1);
+/// assert!(listeners.get_all("scroll").is_empty());
+/// ```
+pub struct FrozenMultiMap<K, V, S = RandomState> {
+    /// Lists of pointers created by `Box::into_raw`, in append order.
+    ///
+    /// Raw pointers are used instead of `Box`, for the same reason as in
+    /// `FrozenSortedVec`: moving a `Box` would invalidate the shared
+    /// references we have handed out.
+    inner: Mutex<HashMap<K, Vec<*mut V>, S>>,
+    phantom: PhantomData<V>,
+}
+
+impl<K, V, S> FrozenMultiMap<K, V, S> {
+    /// Create an empty `FrozenMultiMap` that uses the given hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            inner: Mutex::new(HashMap::with_hasher(hasher)),
+            phantom: PhantomData,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, Vec<*mut V>, S>> {
+        // The lock is only held while running `Hash` and `Eq` impls, which
+        // can't leave the map inconsistent even if they panic.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a reference to a value.
+    fn value_ref(&self, ptr: *mut V) -> &V {
+        // SAFETY: every pointer in the map was created by
+        // `Box::into_raw`, and is only freed when `self` is consumed or
+        // dropped.
+        unsafe { &*ptr }
+    }
+
+    /// Return the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Test whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Consume the map and return its contents.
+    pub fn into_map(mut self) -> HashMap<K, Vec<V>, S>
+    where
+        K: Eq + Hash,
+        S: BuildHasher + Default,
+    {
+        let inner = self.inner.get_mut().unwrap_or_else(PoisonError::into_inner);
+        // Take the pointers, so that `Drop` doesn't free them again.
+        let inner = core::mem::take(inner);
+
+        // SAFETY: every pointer was created by `Box::into_raw`, and we
+        // have exclusive access.
+        inner
+            .into_iter()
+            .map(|(k, vals)| {
+                let vals = vals
+                    .into_iter()
+                    .map(|v| *unsafe { Box::from_raw(v) })
+                    .collect();
+                (k, vals)
+            })
+            .collect()
+    }
+}
+
+impl<K, V, S> FrozenMultiMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Append a value to the list for `key`.
+    ///
+    /// Returns a reference to the appended value.
+    pub fn append(&self, key: K, val: V) -> &V {
+        let ptr = Box::into_raw(Box::new(val));
+        self.lock().entry(key).or_default().push(ptr);
+        self.value_ref(ptr)
+    }
+
+    /// Return references to all values for `key`, in append order.
+    ///
+    /// The result is a snapshot; values appended later won't be included.
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.lock().get(key) {
+            Some(vals) => vals.iter().map(|&v| self.value_ref(v)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the number of values for `key`.
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().get(key).map_or(0, Vec::len)
+    }
+
+    /// Test whether any values exist for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().contains_key(key)
+    }
+}
+
+impl<K, V, S: Default> Default for FrozenMultiMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Drop for FrozenMultiMap<K, V, S> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for &v in inner.values().flatten() {
+            // SAFETY: every pointer was created by `Box::into_raw`, and we
+            // have exclusive access.
+            let _owned = unsafe { Box::from_raw(v) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `FrozenMultiMap` owns its values, like a `HashMap<K, Vec<Box<V>>>`.
+unsafe impl<K: Send, V: Send, S: Send> Send for FrozenMultiMap<K, V, S> {}
+
+// SAFETY: shared references to the values are handed out to any thread,
+// and a value appended by one thread may be dropped by another. Keys are
+// only accessed with the lock held, so they need not be `Sync`.
+unsafe impl<K: Send, V: Send + Sync, S: Send> Sync for FrozenMultiMap<K, V, S> {}
+
+/// Must fail to compile because FrozenMultiMap<_, Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::FrozenMultiMap;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = FrozenMultiMap::<u32, Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenMultiMap;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::thread;
+
+    #[test]
+    fn multimap_test() {
+        let x = FrozenMultiMap::<String, u32>::default();
+        assert!(x.is_empty());
+        let a1 = x.append("a".to_string(), 1);
+        let b1 = x.append("b".to_string(), 10);
+        let a2 = x.append("a".to_string(), 2);
+
+        // References survive later appends.
+        assert_eq!((*a1, *a2, *b1), (1, 2, 10));
+        assert_eq!(x.len(), 2);
+        assert_eq!(x.get_all("a"), [&1, &2]);
+        assert_eq!(x.count("b"), 1);
+        assert_eq!(x.count("c"), 0);
+        assert!(x.contains_key("a"));
+        assert!(!x.contains_key("c"));
+
+        let map = x.into_map();
+        assert_eq!(map["a"], [1, 2]);
+        assert_eq!(map["b"], [10]);
+    }
+
+    #[test]
+    fn concurrent_test() {
+        let x = Arc::new(FrozenMultiMap::<u32, u32>::default());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let x = x.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        x.append(i % 10, t);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(x.len(), 10);
+        for k in 0..10 {
+            assert_eq!(x.count(&k), 40);
+        }
+    }
+}
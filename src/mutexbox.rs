@@ -0,0 +1,152 @@
+//! This is the MutexBox implementation.
+
+use crate::MaybeBox;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `MutexBox` is a lazy-initialized container with interior mutability.
+///
+/// A `MutexBox<T>` can have two possible states:
+/// * uninitialized: [`lock`] will return `None`.
+/// * initialized: [`lock`] will return a guard allowing mutable access
+///   to the `T`.
+///
+/// To upgrade a `MutexBox` to the initialized state, call `lazy_init`.
+/// Like the other containers in this crate, `lazy_init` only requires a
+/// shared reference. Unlike them, `MutexBox` allows the inner value to be
+/// modified after initialization, and can be shared between threads even
+/// if `T` is not `Sync`.
+///
+/// This type requires the `std` feature.
+///
+/// # Panics
+///
+/// Attempting to `lazy_init` more than once will cause a panic.
+///
+/// [`lock`]: MutexBox::lock
+///
+/// # Examples
+/// ```
+/// use freezebox::MutexBox;
+///
+/// let x = MutexBox::<Vec<u32>>::default();
+/// assert!(x.lock().is_none());
+/// x.lazy_init(Vec::new());
+/// x.lock().unwrap().push(1);
+/// assert_eq!(*x.lock().unwrap(), [1]);
+/// ```
+pub struct MutexBox<T> {
+    inner: MaybeBox<Mutex<T>>,
+}
+
+impl<T> MutexBox<T> {
+    /// Create a new `MutexBox` with optional initialization.
+    ///
+    /// To always create an uninitialized `MutexBox`, use
+    /// `MutexBox::default()`.
+    pub fn new(val: Option<T>) -> Self {
+        Self {
+            inner: MaybeBox::new(val.map(Mutex::new)),
+        }
+    }
+
+    /// Create a new `MutexBox` in `const` context
+    ///
+    /// This is the same as `MutexBox::default` except that it works in
+    /// const context, which is desirable for global `static` objects.
+    pub const fn const_default() -> Self {
+        Self {
+            inner: MaybeBox::const_default(),
+        }
+    }
+
+    /// Initialize a `MutexBox`.
+    ///
+    /// The new value will be stored on the heap.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the `MutexBox` is already initialized.
+    /// If it panics, the input value will be dropped.
+    pub fn lazy_init(&self, val: T) {
+        if let Err(val) = self.inner.try_lazy_init(Mutex::new(val)) {
+            drop(val);
+            panic_with_type!("lazy_init on already-initialized MutexBox", T);
+        }
+    }
+
+    /// Lock the `MutexBox`, blocking until the lock is available.
+    ///
+    /// If the `MutexBox` is initialized, this will return a guard that
+    /// gives access to the inner value; otherwise it will return `None`.
+    ///
+    /// If a previous holder of the lock panicked, the lock is still
+    /// acquired; it is up to the caller to decide whether the inner value
+    /// is still usable.
+    pub fn lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.inner
+            .get()
+            .map(|m| m.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Test whether a `MutexBox` is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.inner.is_initialized()
+    }
+
+    /// Consume the `MutexBox` and return its contents.
+    pub fn into_inner(self) -> Option<T> {
+        self.inner
+            .into_inner()
+            .map(|m| m.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl<T> Default for MutexBox<T> {
+    fn default() -> Self {
+        Self {
+            inner: MaybeBox::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MutexBox;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use core::cell::Cell;
+    use std::thread;
+
+    #[test]
+    fn mutexbox_test() {
+        let x = MutexBox::<String>::default();
+        assert!(!x.is_initialized());
+        assert!(x.lock().is_none());
+        x.lazy_init("hello".to_string());
+        assert!(x.is_initialized());
+        x.lock().unwrap().push_str(" world");
+        assert_eq!(*x.lock().unwrap(), "hello world");
+        assert_eq!(x.into_inner().unwrap(), "hello world");
+    }
+
+    #[test]
+    fn shared_not_sync() {
+        // Cell is Send but not Sync; MutexBox makes it shareable.
+        let x = Arc::new(MutexBox::<Cell<u32>>::default());
+        x.lazy_init(Cell::new(1));
+        let x2 = x.clone();
+        thread::spawn(move || x2.lock().unwrap().set(2))
+            .join()
+            .unwrap();
+        assert_eq!(x.lock().unwrap().get(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "lazy_init on already-initialized MutexBox")]
+    fn panic_double_init() {
+        let x = MutexBox::<String>::default();
+        x.lazy_init("first".to_string());
+        x.lazy_init("second".to_string());
+    }
+}
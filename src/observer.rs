@@ -0,0 +1,211 @@
+//! A process-wide hook for observing heap allocations.
+//!
+//! Without the `alloc-observer` feature, the hooks are no-ops, so
+//! containers don't pay for an atomic load on every init and drop.
+
+#[cfg(feature = "alloc-observer")]
+use core::any::type_name;
+#[cfg(feature = "alloc-observer")]
+use core::mem::size_of;
+#[cfg(feature = "alloc-observer")]
+use core::ptr;
+#[cfg(feature = "alloc-observer")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The kind of heap traffic reported by an [`AllocEvent`].
+#[cfg(feature = "alloc-observer")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllocKind {
+    /// A container allocated a box for its value.
+    Alloc,
+    /// A container freed the box holding its value.
+    Free,
+}
+
+/// A report of a container allocating or freeing its boxed value.
+///
+/// This is passed to the observer installed with [`set_alloc_observer`].
+#[cfg(feature = "alloc-observer")]
+#[derive(Clone, Copy, Debug)]
+pub struct AllocEvent {
+    kind: AllocKind,
+    type_name: &'static str,
+    size: usize,
+}
+
+#[cfg(feature = "alloc-observer")]
+impl AllocEvent {
+    /// Whether this is an allocation or a free.
+    pub fn kind(&self) -> AllocKind {
+        self.kind
+    }
+
+    /// The name of the boxed type, as returned by
+    /// [`core::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The size of the boxed value, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// The current observer, stored as a type-erased function pointer.
+#[cfg(feature = "alloc-observer")]
+static OBSERVER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Install a function to be called whenever a container allocates or frees
+/// its boxed value.
+///
+/// This lets embedded and soft-realtime programs audit exactly when lazy
+/// initialization causes heap traffic. It applies to [`FreezeBox`],
+/// [`MaybeBox`] and [`TeardownBox`]. The observer replaces any previous
+/// one, and is called on whichever thread performs the allocation.
+///
+/// A failed initialization (for example, `lazy_init` on a container that
+/// is already initialized) reports both an allocation and a free.
+///
+/// This function requires the `alloc-observer` feature.
+///
+/// [`FreezeBox`]: crate::FreezeBox
+/// [`MaybeBox`]: crate::MaybeBox
+/// [`TeardownBox`]: crate::TeardownBox
+///
+/// # Examples
+/// ```
+/// use freezebox::{set_alloc_observer, AllocEvent, FreezeBox};
+///
+/// fn log_alloc(event: &AllocEvent) {
+///     println!("{:?} {} ({} bytes)", event.kind(), event.type_name(), event.size());
+/// }
+///
+/// set_alloc_observer(log_alloc);
+/// let x = FreezeBox::<u64>::default();
+/// x.lazy_init(1); // prints "Alloc u64 (8 bytes)"
+/// ```
+#[cfg(feature = "alloc-observer")]
+pub fn set_alloc_observer(observer: fn(&AllocEvent)) {
+    OBSERVER.store(observer as *mut (), Ordering::Release);
+}
+
+/// Remove the observer installed with [`set_alloc_observer`].
+///
+/// This function requires the `alloc-observer` feature.
+#[cfg(feature = "alloc-observer")]
+pub fn clear_alloc_observer() {
+    OBSERVER.store(ptr::null_mut(), Ordering::Release);
+}
+
+#[cfg(feature = "alloc-observer")]
+fn notify<T>(kind: AllocKind) {
+    let observer = OBSERVER.load(Ordering::Acquire);
+    if observer.is_null() {
+        return;
+    }
+    // SAFETY: every non-null pointer stored in `OBSERVER` was cast from a
+    // `fn(&AllocEvent)`.
+    let observer: fn(&AllocEvent) = unsafe { core::mem::transmute(observer) };
+    observer(&AllocEvent {
+        kind,
+        type_name: type_name::<T>(),
+        size: size_of::<T>(),
+    });
+}
+
+/// Report that a container allocated a box for a `T`.
+#[inline]
+pub(crate) fn observe_alloc<T>() {
+    #[cfg(feature = "alloc-observer")]
+    notify::<T>(AllocKind::Alloc);
+}
+
+/// Report that a container freed a box holding a `T`.
+#[inline]
+pub(crate) fn observe_free<T>() {
+    #[cfg(feature = "alloc-observer")]
+    notify::<T>(AllocKind::Free);
+}
+
+#[cfg(all(test, feature = "alloc-observer"))]
+mod tests {
+    use super::{set_alloc_observer, AllocEvent, AllocKind};
+    use crate::{FreezeBox, MaybeBox};
+    use core::any::type_name;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    // Other tests may run concurrently, so only count events for a type
+    // that only this test uses.
+    struct Tracked(#[allow(dead_code)] u32);
+
+    static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+    static FREES: AtomicUsize = AtomicUsize::new(0);
+
+    // Used only by `lazy_init_box_test`.
+    struct Boxed(#[allow(dead_code)] u64);
+
+    static BOX_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+    static BOX_FREES: AtomicUsize = AtomicUsize::new(0);
+
+    // Both tests install this observer, so it must handle both types.
+    fn count(event: &AllocEvent) {
+        let (allocs, frees) = if event.type_name() == type_name::<Tracked>() {
+            assert_eq!(event.size(), 4);
+            (&ALLOCS, &FREES)
+        } else if event.type_name() == type_name::<Boxed>() {
+            assert_eq!(event.size(), 8);
+            (&BOX_ALLOCS, &BOX_FREES)
+        } else {
+            return;
+        };
+        match event.kind() {
+            AllocKind::Alloc => allocs.fetch_add(1, Ordering::Relaxed),
+            AllocKind::Free => frees.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    #[test]
+    fn observer_test() {
+        set_alloc_observer(count);
+
+        let x = FreezeBox::<Tracked>::default();
+        x.lazy_init(Tracked(1));
+        assert_eq!(ALLOCS.load(Ordering::Relaxed), 1);
+        assert!(x.into_inner().is_some());
+        assert_eq!(FREES.load(Ordering::Relaxed), 1);
+
+        let y = MaybeBox::new(Some(Tracked(2)));
+        drop(y);
+        assert_eq!(ALLOCS.load(Ordering::Relaxed), 2);
+        assert_eq!(FREES.load(Ordering::Relaxed), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn lazy_init_box_test() {
+        use alloc::boxed::Box;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        set_alloc_observer(count);
+
+        let x = FreezeBox::<Boxed>::default();
+        x.lazy_init_box(Box::new(Boxed(1)));
+        assert_eq!(BOX_ALLOCS.load(Ordering::Relaxed), 1);
+        // A double init reports the allocation and free of the rejected box.
+        let result = catch_unwind(AssertUnwindSafe(|| x.lazy_init_box(Box::new(Boxed(2)))));
+        assert!(result.is_err());
+        assert_eq!(BOX_ALLOCS.load(Ordering::Relaxed), 2);
+        assert_eq!(BOX_FREES.load(Ordering::Relaxed), 1);
+        drop(x);
+        assert_eq!(BOX_FREES.load(Ordering::Relaxed), 2);
+
+        let y = MaybeBox::<Boxed>::default();
+        y.lazy_init_box(Box::new(Boxed(3)));
+        let result = catch_unwind(AssertUnwindSafe(|| y.lazy_init_box(Box::new(Boxed(4)))));
+        assert!(result.is_err());
+        drop(y);
+        assert_eq!(BOX_ALLOCS.load(Ordering::Relaxed), 4);
+        assert_eq!(BOX_FREES.load(Ordering::Relaxed), 4);
+    }
+}
@@ -0,0 +1,178 @@
+//! The `once!` macro.
+
+use crate::MaybeBox;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Run a block of code exactly once per process.
+///
+/// Each `once!` invocation declares a hidden `static`. The first call runs
+/// the block; later calls (and calls that race with the first) wait for it
+/// to finish, then do nothing. This is similar to `std::sync::Once`, but
+/// also works in `no_std` builds.
+///
+/// If the block is written `-> T { ... }`, its result is stored, and every
+/// call returns a `&'static T` to it.
+///
+/// If the block panics, the next call will run it again.
+///
+/// # Examples
+/// ```
+/// use freezebox::once;
+///
+/// fn init_logging() {
+///     once! {
+///         println!("logging initialized");
+///     }
+/// }
+///
+/// fn config() -> &'static Vec<String> {
+///     once!(-> Vec<String> {
+///         vec!["verbose".to_string()]
+///     })
+/// }
+///
+/// init_logging();
+/// init_logging(); // prints nothing
+/// assert!(std::ptr::eq(config(), config()));
+/// ```
+#[macro_export]
+macro_rules! once {
+    (-> $t:ty $body:block) => {{
+        static ONCE: $crate::OnceBlock<$t> = $crate::OnceBlock::new();
+        ONCE.call(|| $body)
+    }};
+    ($($body:tt)*) => {{
+        let _: &'static () = $crate::once!(-> () { $($body)* });
+    }};
+}
+
+/// The storage behind the [`once!`] macro.
+///
+/// This is an implementation detail, and is not part of the public API.
+#[doc(hidden)]
+pub struct OnceBlock<T> {
+    /// Set while a thread is running the block.
+    running: AtomicBool,
+    value: MaybeBox<T>,
+}
+
+impl<T> OnceBlock<T> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+            value: MaybeBox::const_default(),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn call<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        let mut f = Some(f);
+        loop {
+            if let Some(val) = self.value.get() {
+                return val;
+            }
+            if self
+                .running
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Acquire)
+                .is_ok()
+            {
+                // If the block panics, allow another call to retry.
+                let reset = ResetOnPanic(&self.running);
+                let f = f.take().unwrap();
+                let val = self.value.lazy_init(f());
+                // `running` stays set, so that no other thread will
+                // try to initialize `value` again.
+                core::mem::forget(reset);
+                return val;
+            }
+            relax();
+        }
+    }
+}
+
+/// Clears the running flag, if the block panics.
+struct ResetOnPanic<'a>(&'a AtomicBool);
+
+impl Drop for ResetOnPanic<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Wait a little while another thread runs the block.
+fn relax() {
+    #[cfg(feature = "std")]
+    std::thread::yield_now();
+    #[cfg(not(feature = "std"))]
+    #[allow(deprecated)]
+    core::sync::atomic::spin_loop_hint();
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn once_test() {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let run = || {
+            once! {
+                COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+        };
+        run();
+        run();
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn once_value_test() {
+        fn value() -> &'static u32 {
+            once!(-> u32 { 42 })
+        }
+        assert_eq!(*value(), 42);
+        assert!(core::ptr::eq(value(), value()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn once_panic_test() {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let run = || {
+            *once!(-> usize {
+                let n = COUNT.fetch_add(1, Ordering::Relaxed);
+                assert!(n > 0, "first attempt fails");
+                n
+            })
+        };
+        assert!(std::panic::catch_unwind(run).is_err());
+        assert_eq!(run(), 1);
+        assert_eq!(run(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn once_race_test() {
+        use alloc::vec::Vec;
+
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    *once!(-> usize {
+                        std::thread::sleep(core::time::Duration::from_millis(10));
+                        COUNT.fetch_add(1, Ordering::Relaxed)
+                    })
+                })
+            })
+            .collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 0);
+        }
+        assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,165 @@
+//! This is the OnceEvent implementation.
+
+use crate::waiter::{Signal, Wait, Waiters};
+use crate::Error;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// `OnceEvent` is a one-shot latch that carries no data.
+///
+/// An `OnceEvent` starts out unset. Once `set` is called, it stays set
+/// forever, and everyone waiting for it is woken up. This is useful for
+/// signaling a condition like "the initialization phase is complete".
+///
+/// Both blocking ([`wait`]) and async ([`wait_async`]) waiting are
+/// supported.
+///
+/// This type requires the `std` feature.
+///
+/// [`wait`]: OnceEvent::wait
+/// [`wait_async`]: OnceEvent::wait_async
+///
+/// # Examples
+/// ```
+/// use freezebox::OnceEvent;
+/// use std::sync::Arc;
+///
+/// let ready = Arc::new(OnceEvent::new());
+/// let ready2 = ready.clone();
+///
+/// let handle = std::thread::spawn(move || {
+///     ready2.wait();
+///     assert!(ready2.is_set());
+/// });
+///
+/// ready.set();
+/// handle.join().unwrap();
+/// ```
+#[derive(Default)]
+pub struct OnceEvent {
+    flag: AtomicBool,
+    waiters: Waiters,
+}
+
+impl OnceEvent {
+    /// Create a new, unset `OnceEvent`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of times a blocking wait checks the event before
+    /// putting the thread to sleep.
+    ///
+    /// Events are usually set soon after a waiter arrives, so a short spin
+    /// avoids the latency of parking and unparking the thread. A limit of
+    /// zero disables spinning. The default is 100.
+    pub fn with_spin_limit(mut self, spin_limit: u32) -> Self {
+        self.waiters.set_spin_limit(spin_limit);
+        self
+    }
+
+    /// Set the event, waking everyone who is waiting for it.
+    ///
+    /// Setting an event that is already set has no effect.
+    pub fn set(&self) {
+        // `Release` ordering ensures that anything done before `set` is
+        // visible to threads that observe the event as set.
+        if !self.flag.swap(true, Ordering::AcqRel) {
+            self.waiters.notify_all();
+        }
+    }
+
+    /// Test whether the event is set.
+    pub fn is_set(&self) -> bool {
+        self.flag.load(Ordering::Acquire)
+    }
+
+    /// Block the current thread until the event is set.
+    pub fn wait(&self) {
+        self.waiters.wait(|| self.is_set());
+    }
+
+    /// Block the current thread until the event is set, or until
+    /// `timeout` has elapsed.
+    ///
+    /// Returns [`Error::Timeout`] if the timeout expired first.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<(), Error> {
+        if self.waiters.wait_timeout(|| self.is_set(), timeout) {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Wait asynchronously until the event is set.
+    pub fn wait_async(&self) -> Wait<'_> {
+        Wait::new(self)
+    }
+}
+
+impl Signal for OnceEvent {
+    fn is_ready(&self) -> bool {
+        self.is_set()
+    }
+
+    fn waiters(&self) -> &Waiters {
+        &self.waiters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OnceEvent;
+    use crate::waiter::block_on;
+    use crate::Error;
+    use alloc::sync::Arc;
+    use core::time::Duration;
+    use std::thread;
+
+    #[test]
+    fn onceevent_test() {
+        let x = Arc::new(OnceEvent::new());
+        assert!(!x.is_set());
+        assert_eq!(
+            x.wait_timeout(Duration::from_millis(1)),
+            Err(Error::Timeout)
+        );
+
+        let handles: alloc::vec::Vec<_> = (0..4)
+            .map(|_| {
+                let x = x.clone();
+                thread::spawn(move || x.wait())
+            })
+            .collect();
+
+        x.set();
+        x.set();
+        assert!(x.is_set());
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(x.wait_timeout(Duration::from_millis(1)), Ok(()));
+    }
+
+    #[test]
+    fn spin_limit_test() {
+        for &spins in &[0, 1_000_000] {
+            let x = Arc::new(OnceEvent::new().with_spin_limit(spins));
+            let x2 = x.clone();
+            let handle = thread::spawn(move || x2.wait());
+            x.set();
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn async_test() {
+        let x = Arc::new(OnceEvent::new());
+        let x2 = x.clone();
+        let handle = thread::spawn(move || block_on(x2.wait_async()));
+        thread::sleep(Duration::from_millis(10));
+        x.set();
+        handle.join().unwrap();
+        block_on(x.wait_async());
+    }
+}
@@ -0,0 +1,153 @@
+//! This is the OwnedRef implementation.
+
+use crate::FreezeBox;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+impl<T> FreezeBox<T> {
+    /// Get an owning reference to the contents of a shared `FreezeBox`.
+    ///
+    /// The returned [`OwnedRef`] keeps the `FreezeBox` alive, so it can be
+    /// returned from functions or stored without borrowing from the
+    /// `Arc`. Returns `None` if the `FreezeBox` is uninitialized.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::{FreezeBox, OwnedRef};
+    /// use std::sync::Arc;
+    ///
+    /// fn config_name(config: &Arc<FreezeBox<String>>) -> OwnedRef<String> {
+    ///     config.get_owned().unwrap()
+    /// }
+    ///
+    /// let config = Arc::new(FreezeBox::new(Some("prod".to_string())));
+    /// let name = config_name(&config);
+    /// drop(config);
+    /// assert_eq!(*name, "prod");
+    /// ```
+    pub fn get_owned(self: &Arc<Self>) -> Option<OwnedRef<T>> {
+        if !self.is_initialized() {
+            return None;
+        }
+        let value: *const T = &***self;
+        Some(OwnedRef {
+            _owner: Arc::clone(self),
+            value,
+        })
+    }
+}
+
+/// An owning reference to the contents of a [`FreezeBox`].
+///
+/// Created by [`FreezeBox::get_owned`]. An `OwnedRef` holds an `Arc` to
+/// the `FreezeBox`, and derefs to the value inside, or to part of it (see
+/// [`map`][OwnedRef::map]).
+pub struct OwnedRef<T, U: ?Sized = T> {
+    _owner: Arc<FreezeBox<T>>,
+    /// Points into the contents of `_owner`, which can't be removed while
+    /// `_owner` is shared.
+    value: *const U,
+}
+
+impl<T, U: ?Sized> OwnedRef<T, U> {
+    /// Narrow an `OwnedRef` to a part of the value, such as a field.
+    ///
+    /// The new `OwnedRef` still keeps the whole `FreezeBox` alive, but
+    /// only exposes the part returned by `f`.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::{FreezeBox, OwnedRef};
+    /// use std::sync::Arc;
+    ///
+    /// struct Config {
+    ///     name: String,
+    ///     secret: String,
+    /// }
+    ///
+    /// let config = Arc::new(FreezeBox::new(Some(Config {
+    ///     name: "prod".to_string(),
+    ///     secret: "hunter2".to_string(),
+    /// })));
+    ///
+    /// let name: OwnedRef<Config, String> = config.get_owned().unwrap().map(|c| &c.name);
+    /// assert_eq!(*name, "prod");
+    /// ```
+    pub fn map<V: ?Sized, F>(self, f: F) -> OwnedRef<T, V>
+    where
+        F: FnOnce(&U) -> &V,
+    {
+        let value: *const V = f(&*self);
+        OwnedRef {
+            _owner: self._owner,
+            value,
+        }
+    }
+}
+
+impl<T, U: ?Sized> Clone for OwnedRef<T, U> {
+    fn clone(&self) -> Self {
+        Self {
+            _owner: Arc::clone(&self._owner),
+            value: self.value,
+        }
+    }
+}
+
+impl<T, U: ?Sized> Deref for OwnedRef<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `value` points into the initialized contents of the
+        // `FreezeBox`, which can only be removed by consuming it. We hold
+        // an `Arc` to it, so that can't happen while `self` lives. `map`
+        // only accepts functions that return references that live as long
+        // as their input.
+        unsafe { &*self.value }
+    }
+}
+
+impl<T, U: fmt::Debug + ?Sized> fmt::Debug for OwnedRef<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedRef").field(&&**self).finish()
+    }
+}
+
+// SAFETY: an `OwnedRef` is equivalent to an `Arc<FreezeBox<T>>` plus a
+// shared reference to the `U` inside.
+unsafe impl<T: Send + Sync, U: Sync + ?Sized> Send for OwnedRef<T, U> {}
+unsafe impl<T: Send + Sync, U: Sync + ?Sized> Sync for OwnedRef<T, U> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::FreezeBox;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn ownedref_test() {
+        let x = Arc::new(FreezeBox::<String>::default());
+        assert!(x.get_owned().is_none());
+        x.lazy_init("hello".to_string());
+
+        let y = x.get_owned().unwrap();
+        let z = y.clone();
+        assert_eq!(Arc::strong_count(&x), 3);
+        drop(x);
+        assert_eq!(*y, "hello");
+        assert_eq!(format!("{:?}", z), "OwnedRef(\"hello\")");
+    }
+
+    #[test]
+    fn map_test() {
+        let x = Arc::new(FreezeBox::new(Some(("hello".to_string(), 5))));
+        let first = x.get_owned().unwrap().map(|pair| &pair.0);
+        let tail = first.clone().map(|s| &s[1..]);
+        drop(x);
+        assert_eq!(*first, "hello");
+        assert_eq!(&*tail, "ello");
+    }
+}
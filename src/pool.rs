@@ -0,0 +1,285 @@
+//! This is the LazyPool implementation.
+
+use crate::waiter::Waiters;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `LazyPool` is a pool of reusable objects that are created on demand.
+///
+/// Objects are created by a stored factory function the first time they
+/// are needed, up to a fixed capacity. Checking out an object returns a
+/// [`PoolGuard`], which gives exclusive access to the object and returns
+/// it to the pool when dropped.
+///
+/// Like the other containers in this crate, a `LazyPool` only needs a
+/// shared reference, so it can be stored in a `static` (via a
+/// [`FreezeBox`][crate::FreezeBox]) or shared with an `Arc`.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::LazyPool;
+///
+/// let pool = LazyPool::new(2, || Vec::<u8>::with_capacity(4096));
+///
+/// let mut a = pool.get();
+/// let b = pool.get();
+/// assert!(pool.try_get().is_none());
+///
+/// a.extend_from_slice(b"hello");
+/// drop(a);
+///
+/// // The buffer is reused, not recreated.
+/// let c = pool.try_get().unwrap();
+/// assert_eq!(&*c, b"hello");
+/// assert_eq!(pool.created(), 2);
+/// # drop(b);
+/// ```
+pub struct LazyPool<T, F = fn() -> T> {
+    /// Objects that have been created and are not checked out.
+    idle: Mutex<Vec<T>>,
+    /// The number of objects that exist (or are being created).
+    created: AtomicUsize,
+    capacity: usize,
+    factory: F,
+    waiters: Waiters,
+}
+
+impl<T, F> LazyPool<T, F>
+where
+    F: Fn() -> T,
+{
+    /// Create a new, empty `LazyPool`.
+    ///
+    /// No objects are created until they are checked out. At most
+    /// `capacity` objects will ever be created.
+    pub fn new(capacity: usize, factory: F) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            created: AtomicUsize::new(0),
+            capacity,
+            factory,
+            waiters: Waiters::default(),
+        }
+    }
+
+    /// Reserve the right to create a new object.
+    ///
+    /// Returns false if the pool is already at capacity.
+    fn reserve(&self) -> bool {
+        let capacity = self.capacity;
+        self.created
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                if n < capacity {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    /// Check out an object, without blocking.
+    ///
+    /// An idle object is reused if one is available. Otherwise, if the pool
+    /// is below capacity, a new object is created by calling the factory.
+    /// Returns `None` if every object is checked out and the pool is at
+    /// capacity.
+    pub fn try_get(&self) -> Option<PoolGuard<'_, T, F>> {
+        if let Some(val) = self.lock().pop() {
+            return Some(PoolGuard::new(self, val));
+        }
+        if !self.reserve() {
+            return None;
+        }
+
+        // If the factory panics, give back the reservation.
+        let unreserve = Unreserve(self);
+        let val = (self.factory)();
+        core::mem::forget(unreserve);
+        Some(PoolGuard::new(self, val))
+    }
+
+    /// Check out an object, blocking the current thread until one is
+    /// available.
+    pub fn get(&self) -> PoolGuard<'_, T, F> {
+        loop {
+            if let Some(guard) = self.try_get() {
+                return guard;
+            }
+            self.waiters.wait(|| self.is_available());
+        }
+    }
+
+    /// Test whether `try_get` would (probably) succeed.
+    fn is_available(&self) -> bool {
+        self.created.load(Ordering::Acquire) < self.capacity || !self.lock().is_empty()
+    }
+}
+
+impl<T, F> LazyPool<T, F> {
+    fn lock(&self) -> MutexGuard<'_, Vec<T>> {
+        // The lock is never held while running user code, so poisoning
+        // can't leave the pool inconsistent.
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the maximum number of objects the pool will create.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Return the number of objects that have been created.
+    pub fn created(&self) -> usize {
+        self.created.load(Ordering::Acquire)
+    }
+
+    /// Return the number of created objects that are not checked out.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Return an object to the pool, waking any threads waiting for one.
+    fn put(&self, val: T) {
+        self.lock().push(val);
+        self.waiters.notify_all();
+    }
+}
+
+impl<T, F> fmt::Debug for LazyPool<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyPool")
+            .field("capacity", &self.capacity)
+            .field("created", &self.created())
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+/// Gives back a reservation made by `LazyPool::reserve`, if the factory
+/// panics.
+struct Unreserve<'a, T, F>(&'a LazyPool<T, F>);
+
+impl<T, F> Drop for Unreserve<'_, T, F> {
+    fn drop(&mut self) {
+        self.0.created.fetch_sub(1, Ordering::AcqRel);
+        self.0.waiters.notify_all();
+    }
+}
+
+/// An object checked out of a [`LazyPool`].
+///
+/// Created by [`LazyPool::get`] or [`LazyPool::try_get`]. The object is
+/// returned to the pool when the guard is dropped.
+pub struct PoolGuard<'a, T, F = fn() -> T> {
+    pool: &'a LazyPool<T, F>,
+    value: ManuallyDrop<T>,
+}
+
+impl<'a, T, F> PoolGuard<'a, T, F> {
+    fn new(pool: &'a LazyPool<T, F>, val: T) -> Self {
+        Self {
+            pool,
+            value: ManuallyDrop::new(val),
+        }
+    }
+}
+
+impl<T, F> Deref for PoolGuard<'_, T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, F> DerefMut for PoolGuard<'_, T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, F> Drop for PoolGuard<'_, T, F> {
+    fn drop(&mut self) {
+        // SAFETY: `value` is never used again after this.
+        let val = unsafe { ManuallyDrop::take(&mut self.value) };
+        self.pool.put(val);
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for PoolGuard<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PoolGuard").field(&*self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LazyPool;
+    use alloc::format;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn pool_test() {
+        let made = AtomicUsize::new(0);
+        let pool = LazyPool::new(2, || made.fetch_add(1, Ordering::Relaxed));
+        assert_eq!(pool.created(), 0);
+
+        let a = pool.get();
+        let b = pool.try_get().unwrap();
+        assert_eq!((*a, *b), (0, 1));
+        assert!(pool.try_get().is_none());
+        assert_eq!(format!("{:?}", b), "PoolGuard(1)");
+
+        drop(a);
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(*pool.get(), 0);
+        assert_eq!(made.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            format!("{:?}", pool),
+            "LazyPool { capacity: 2, created: 2, idle: 1 }"
+        );
+    }
+
+    #[test]
+    fn factory_panic_test() {
+        let pool = LazyPool::new(1, || -> u32 { panic!("no resources") });
+        let result = std::panic::catch_unwind(|| {
+            let _ = pool.try_get();
+        });
+        assert!(result.is_err());
+        assert_eq!(pool.created(), 0);
+    }
+
+    #[test]
+    fn blocking_test() {
+        let pool = Arc::new(LazyPool::new(2, Vec::<u32>::new));
+        let workers: Vec<_> = (0..8)
+            .map(|i| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        pool.get().push(i);
+                    }
+                })
+            })
+            .collect();
+        for w in workers {
+            w.join().unwrap();
+        }
+        assert!(pool.created() <= 2);
+        let mut guards = Vec::new();
+        while let Some(guard) = pool.try_get() {
+            guards.push(guard);
+        }
+        assert_eq!(guards.iter().map(|g| g.len()).sum::<usize>(), 800);
+    }
+}
@@ -0,0 +1,342 @@
+//! Allocation-free once cells for references and small values.
+
+use crate::Error;
+use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// `OnceNonZeroUsize` is a write-once `NonZeroUsize`, stored inline.
+///
+/// Unlike [`FreezeBox`][crate::FreezeBox], no heap allocation is needed.
+/// If multiple threads race to initialize it with [`get_or_init`], each
+/// may run its initializer, but only the first value stored is kept.
+///
+/// [`get_or_init`]: OnceNonZeroUsize::get_or_init
+///
+/// # Examples
+/// ```
+/// use freezebox::OnceNonZeroUsize;
+/// use std::num::NonZeroUsize;
+///
+/// static PAGE_SIZE: OnceNonZeroUsize = OnceNonZeroUsize::new();
+///
+/// let size = PAGE_SIZE.get_or_init(|| NonZeroUsize::new(4096).unwrap());
+/// assert_eq!(size.get(), 4096);
+/// ```
+#[derive(Debug, Default)]
+pub struct OnceNonZeroUsize {
+    inner: AtomicUsize,
+}
+
+impl OnceNonZeroUsize {
+    /// Create a new, uninitialized `OnceNonZeroUsize`.
+    pub const fn new() -> Self {
+        Self {
+            inner: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the value, if it has been initialized.
+    pub fn get(&self) -> Option<NonZeroUsize> {
+        NonZeroUsize::new(self.inner.load(Ordering::Acquire))
+    }
+
+    /// Initialize the value.
+    ///
+    /// Returns [`Error::AlreadyInitialized`] if it was already initialized.
+    pub fn set(&self, val: NonZeroUsize) -> Result<(), Error> {
+        // The atomic ordering here is the same as `FreezeBox::lazy_init`.
+        crate::chaos::delay();
+        self.inner
+            .compare_exchange(0, val.get(), Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| Error::AlreadyInitialized)
+    }
+
+    /// Get the value, initializing it with `f` if necessary.
+    ///
+    /// If another thread initializes the value while `f` is running, the
+    /// result of `f` is discarded, and the other thread's value is
+    /// returned.
+    pub fn get_or_init<F>(&self, f: F) -> NonZeroUsize
+    where
+        F: FnOnce() -> NonZeroUsize,
+    {
+        self.get_or_init_with_status(f).0
+    }
+
+    /// Get the value, initializing it with `f` if necessary, and report
+    /// whether this call initialized it.
+    ///
+    /// The `bool` is true if the value returned by `f` was stored. It is
+    /// false if the value was already initialized, or if another thread
+    /// initialized it while `f` was running.
+    pub fn get_or_init_with_status<F>(&self, f: F) -> (NonZeroUsize, bool)
+    where
+        F: FnOnce() -> NonZeroUsize,
+    {
+        if let Some(val) = self.get() {
+            return (val, false);
+        }
+        let val = f();
+        crate::chaos::delay();
+        match self
+            .inner
+            .compare_exchange(0, val.get(), Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => (val, true),
+            // SAFETY: the exchange only fails if the value is nonzero.
+            Err(existing) => (unsafe { NonZeroUsize::new_unchecked(existing) }, false),
+        }
+    }
+}
+
+/// `OnceBool` is a write-once `bool`, stored inline.
+///
+/// This has the same racing behavior as [`OnceNonZeroUsize`].
+///
+/// # Examples
+/// ```
+/// use freezebox::OnceBool;
+///
+/// static HAS_AVX: OnceBool = OnceBool::new();
+///
+/// assert_eq!(HAS_AVX.get(), None);
+/// assert!(!HAS_AVX.get_or_init(|| false));
+/// assert_eq!(HAS_AVX.get(), Some(false));
+/// ```
+#[derive(Debug, Default)]
+pub struct OnceBool {
+    inner: OnceNonZeroUsize,
+}
+
+impl OnceBool {
+    /// Create a new, uninitialized `OnceBool`.
+    pub const fn new() -> Self {
+        Self {
+            inner: OnceNonZeroUsize::new(),
+        }
+    }
+
+    fn to_usize(val: bool) -> NonZeroUsize {
+        // SAFETY: both values are nonzero.
+        unsafe { NonZeroUsize::new_unchecked(if val { 2 } else { 1 }) }
+    }
+
+    fn from_usize(val: NonZeroUsize) -> bool {
+        val.get() == 2
+    }
+
+    /// Get the value, if it has been initialized.
+    pub fn get(&self) -> Option<bool> {
+        self.inner.get().map(Self::from_usize)
+    }
+
+    /// Initialize the value.
+    ///
+    /// Returns [`Error::AlreadyInitialized`] if it was already initialized.
+    pub fn set(&self, val: bool) -> Result<(), Error> {
+        self.inner.set(Self::to_usize(val))
+    }
+
+    /// Get the value, initializing it with `f` if necessary.
+    ///
+    /// If another thread initializes the value while `f` is running, the
+    /// result of `f` is discarded, and the other thread's value is
+    /// returned.
+    pub fn get_or_init<F>(&self, f: F) -> bool
+    where
+        F: FnOnce() -> bool,
+    {
+        self.get_or_init_with_status(f).0
+    }
+
+    /// Get the value, initializing it with `f` if necessary, and report
+    /// whether this call initialized it.
+    ///
+    /// See [`OnceNonZeroUsize::get_or_init_with_status`].
+    pub fn get_or_init_with_status<F>(&self, f: F) -> (bool, bool)
+    where
+        F: FnOnce() -> bool,
+    {
+        let (val, initialized) = self.inner.get_or_init_with_status(|| Self::to_usize(f()));
+        (Self::from_usize(val), initialized)
+    }
+}
+
+/// `OnceRef` is a write-once shared reference, stored inline.
+///
+/// This has the same racing behavior as [`OnceNonZeroUsize`]. Because it
+/// only stores a reference, the referenced value must be owned elsewhere,
+/// such as in a `static`.
+///
+/// # Examples
+/// ```
+/// use freezebox::OnceRef;
+///
+/// static ENGLISH: &[&str] = &["hello", "goodbye"];
+/// static WORDS: OnceRef<&[&str]> = OnceRef::new();
+///
+/// WORDS.set(&ENGLISH).unwrap();
+/// assert_eq!(WORDS.get().unwrap()[0], "hello");
+/// ```
+pub struct OnceRef<'a, T> {
+    inner: AtomicPtr<T>,
+    phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T> OnceRef<'a, T> {
+    /// Create a new, uninitialized `OnceRef`.
+    pub const fn new() -> Self {
+        Self {
+            inner: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the reference, if it has been initialized.
+    pub fn get(&self) -> Option<&'a T> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or was created from a
+        // `&'a T`.
+        unsafe { ptr.as_ref() }
+    }
+
+    /// Initialize the reference.
+    ///
+    /// Returns [`Error::AlreadyInitialized`] if it was already initialized.
+    pub fn set(&self, val: &'a T) -> Result<(), Error> {
+        let ptr = val as *const T as *mut T;
+        crate::chaos::delay();
+        self.inner
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| Error::AlreadyInitialized)
+    }
+
+    /// Get the reference, initializing it with `f` if necessary.
+    ///
+    /// If another thread initializes the reference while `f` is running,
+    /// the result of `f` is discarded, and the other thread's reference is
+    /// returned.
+    pub fn get_or_init<F>(&self, f: F) -> &'a T
+    where
+        F: FnOnce() -> &'a T,
+    {
+        self.get_or_init_with_status(f).0
+    }
+
+    /// Get the reference, initializing it with `f` if necessary, and
+    /// report whether this call initialized it.
+    ///
+    /// See [`OnceNonZeroUsize::get_or_init_with_status`].
+    pub fn get_or_init_with_status<F>(&self, f: F) -> (&'a T, bool)
+    where
+        F: FnOnce() -> &'a T,
+    {
+        if let Some(val) = self.get() {
+            return (val, false);
+        }
+        let val = f();
+        let ptr = val as *const T as *mut T;
+        crate::chaos::delay();
+        match self
+            .inner
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => (val, true),
+            // SAFETY: the exchange only fails if the pointer is non-null,
+            // in which case it was created from a `&'a T`.
+            Err(existing) => (unsafe { &*existing }, false),
+        }
+    }
+}
+
+impl<T> Default for OnceRef<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for OnceRef<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.get() {
+            Some(val) => f.debug_tuple("OnceRef").field(val).finish(),
+            None => f.write_str("OnceRef(<uninitialized>)"),
+        }
+    }
+}
+
+/// Must fail to compile because OnceRef<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::OnceRef;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = OnceRef::<Cell<u32>>::new();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::{OnceBool, OnceNonZeroUsize, OnceRef};
+    use crate::Error;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn nonzero_test() {
+        let x = OnceNonZeroUsize::new();
+        let five = NonZeroUsize::new(5).unwrap();
+        let six = NonZeroUsize::new(6).unwrap();
+        assert_eq!(x.get(), None);
+        assert_eq!(x.get_or_init(|| five), five);
+        assert_eq!(x.get_or_init(|| unreachable!()), five);
+        assert_eq!(x.set(six), Err(Error::AlreadyInitialized));
+        assert_eq!(x.get(), Some(five));
+    }
+
+    #[test]
+    fn with_status_test() {
+        static A: u32 = 1;
+        let x = OnceNonZeroUsize::new();
+        let five = NonZeroUsize::new(5).unwrap();
+        assert_eq!(x.get_or_init_with_status(|| five), (five, true));
+        assert_eq!(x.get_or_init_with_status(|| unreachable!()), (five, false));
+
+        let y = OnceBool::new();
+        assert_eq!(y.get_or_init_with_status(|| false), (false, true));
+        assert_eq!(y.get_or_init_with_status(|| true), (false, false));
+
+        let z = OnceRef::new();
+        assert_eq!(z.get_or_init_with_status(|| &A), (&1, true));
+        assert_eq!(z.get_or_init_with_status(|| &A), (&1, false));
+    }
+
+    #[test]
+    fn bool_test() {
+        let x = OnceBool::new();
+        assert_eq!(x.get(), None);
+        x.set(true).unwrap();
+        assert_eq!(x.set(false), Err(Error::AlreadyInitialized));
+        assert_eq!(x.get(), Some(true));
+        assert!(x.get_or_init(|| false));
+
+        let y = OnceBool::default();
+        assert!(!y.get_or_init(|| false));
+        assert_eq!(y.get(), Some(false));
+    }
+
+    #[test]
+    fn ref_test() {
+        static A: u32 = 1;
+        static B: u32 = 2;
+        static X: OnceRef<u32> = OnceRef::new();
+        assert_eq!(X.get(), None);
+        assert_eq!(*X.get_or_init(|| &A), 1);
+        assert_eq!(X.set(&B), Err(Error::AlreadyInitialized));
+        assert!(core::ptr::eq(X.get().unwrap(), &A));
+    }
+}
@@ -0,0 +1,98 @@
+//! This is the Redacted implementation.
+
+use core::any::type_name;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// `Redacted` is a wrapper that hides its contents from formatted output.
+///
+/// The `Debug` and `Display` implementations of `Redacted<T>` print
+/// `[REDACTED <type>]` instead of the inner value. This is useful for
+/// lazy-initialized fields holding secrets like passwords or tokens, which
+/// should never end up in logs.
+///
+/// `Redacted` implements `Deref`, so the inner value is still easy to use.
+///
+/// # Examples
+/// ```
+/// use freezebox::{FreezeBox, Redacted};
+///
+/// let token = FreezeBox::<Redacted<String>>::default();
+/// token.lazy_init(Redacted::new("hunter2".to_string()));
+///
+/// assert_eq!(token.len(), 7);
+/// let debug = format!("{:?}", token);
+/// assert!(!debug.contains("hunter2"));
+/// assert!(debug.contains("[REDACTED"));
+/// ```
+#[derive(Clone, Default)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    /// Wrap a value so that it is hidden from formatted output.
+    pub const fn new(val: T) -> Self {
+        Self(val)
+    }
+
+    /// Consume the `Redacted` and return the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(val: T) -> Self {
+        Self(val)
+    }
+}
+
+impl<T> Deref for Redacted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Redacted<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[REDACTED {}]", type_name::<T>())
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[REDACTED {}]", type_name::<T>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Redacted;
+    use crate::{FreezeBox, MaybeBox};
+    use alloc::format;
+    use alloc::string::ToString;
+
+    #[test]
+    fn redacted_test() {
+        let x = Redacted::new("secret".to_string());
+        assert_eq!(x.len(), 6);
+        assert_eq!(format!("{:?}", x), "[REDACTED alloc::string::String]");
+        assert_eq!(format!("{}", x), "[REDACTED alloc::string::String]");
+        assert_eq!(x.into_inner(), "secret");
+    }
+
+    #[test]
+    fn container_test() {
+        let x = MaybeBox::new(Some(Redacted::new(1234u32)));
+        assert_eq!(format!("{:?}", x), "MaybeBox([REDACTED u32])");
+        let y = FreezeBox::new(Some(Redacted::new(1234u32)));
+        assert_eq!(format!("{:?}", y), "FreezeBox([REDACTED u32])");
+    }
+}
@@ -0,0 +1,216 @@
+//! This is the NamedRegistry implementation.
+
+use crate::waiter::Waiters;
+use crate::Error;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `NamedRegistry` is a set of write-once values, looked up by name.
+///
+/// Components register values under string names, using only a shared
+/// reference. Other components can look them up, or wait until they are
+/// registered. Once registered, a value can never be replaced or removed,
+/// so references to it remain valid for as long as the registry lives.
+///
+/// Because values are found by name rather than by `TypeId`, this works
+/// across dynamic library boundaries, as long as every component agrees
+/// on the value type `T` (often a trait object, like
+/// `Box<dyn Service + Send + Sync>`).
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::NamedRegistry;
+/// use std::sync::Arc;
+///
+/// let registry = Arc::new(NamedRegistry::<String>::default());
+/// let registry2 = registry.clone();
+///
+/// let consumer = std::thread::spawn(move || registry2.wait("db.url").clone());
+///
+/// registry.register("db.url", "postgres://localhost".to_string()).unwrap();
+/// assert_eq!(consumer.join().unwrap(), "postgres://localhost");
+///
+/// // Names can only be registered once.
+/// assert!(registry.register("db.url", String::new()).is_err());
+/// ```
+pub struct NamedRegistry<T> {
+    /// Pointers created by `Box::into_raw`.
+    ///
+    /// Raw pointers are used instead of `Box`, for the same reason as in
+    /// `FrozenSortedVec`: moving a `Box` would invalidate the shared
+    /// references we have handed out.
+    inner: Mutex<HashMap<String, *mut T>>,
+    waiters: Waiters,
+    phantom: PhantomData<T>,
+}
+
+impl<T> NamedRegistry<T> {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, *mut T>> {
+        // The lock is never held while running user code, so poisoning
+        // can't leave the map inconsistent.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a reference to a value.
+    fn value_ref(&self, ptr: *mut T) -> &T {
+        // SAFETY: every pointer in the map was created by
+        // `Box::into_raw`, and is only freed when `self` is dropped.
+        unsafe { &*ptr }
+    }
+
+    /// Register a value under `name`.
+    ///
+    /// Returns a reference to the registered value. If `name` was already
+    /// registered, the registry is unchanged and `val` is returned in the
+    /// `Err` variant.
+    pub fn register(&self, name: &str, val: T) -> Result<&T, T> {
+        let ptr = {
+            let mut inner = self.lock();
+            if inner.contains_key(name) {
+                return Err(val);
+            }
+            let ptr = Box::into_raw(Box::new(val));
+            inner.insert(String::from(name), ptr);
+            ptr
+        };
+        self.waiters.notify_all();
+        Ok(self.value_ref(ptr))
+    }
+
+    /// Get the value registered under `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.lock().get(name).map(|&ptr| self.value_ref(ptr))
+    }
+
+    /// Test whether `name` has been registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.lock().contains_key(name)
+    }
+
+    /// Get the value registered under `name`, blocking the current thread
+    /// until it has been registered.
+    pub fn wait(&self, name: &str) -> &T {
+        self.waiters.wait(|| self.contains(name));
+        self.get(name).unwrap()
+    }
+
+    /// Get the value registered under `name`, blocking the current thread
+    /// until it has been registered, or until `timeout` has elapsed.
+    ///
+    /// Returns [`Error::Timeout`] if the timeout expired first.
+    pub fn wait_timeout(&self, name: &str, timeout: Duration) -> Result<&T, Error> {
+        if self.waiters.wait_timeout(|| self.contains(name), timeout) {
+            Ok(self.get(name).unwrap())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Return the names that have been registered, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Return the number of registered values.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Test whether the registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+}
+
+impl<T> Default for NamedRegistry<T> {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(HashMap::new()),
+            waiters: Waiters::default(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for NamedRegistry<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for &v in inner.values() {
+            // SAFETY: every pointer was created by `Box::into_raw`, and we
+            // have exclusive access.
+            let _owned = unsafe { Box::from_raw(v) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `NamedRegistry` owns its values, like a `HashMap<String, Box<T>>`.
+unsafe impl<T: Send> Send for NamedRegistry<T> {}
+
+// SAFETY: shared references to the values are handed out to any thread,
+// and a value registered by one thread may be dropped by another.
+unsafe impl<T: Send + Sync> Sync for NamedRegistry<T> {}
+
+/// Must fail to compile because NamedRegistry<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::NamedRegistry;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = NamedRegistry::<Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::NamedRegistry;
+    use crate::Error;
+    use alloc::sync::Arc;
+    use core::time::Duration;
+    use std::thread;
+
+    #[test]
+    fn registry_test() {
+        let x = NamedRegistry::<u32>::default();
+        assert!(x.is_empty());
+        let a = x.register("a", 1).unwrap();
+        assert_eq!(x.register("a", 2), Err(2));
+        x.register("b", 3).unwrap();
+
+        // References survive later registrations.
+        assert_eq!(*a, 1);
+        assert_eq!(x.get("b"), Some(&3));
+        assert_eq!(x.get("c"), None);
+        assert!(x.contains("a"));
+        assert_eq!(x.len(), 2);
+        let mut names = x.names();
+        names.sort();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(x.wait("a"), &1);
+    }
+
+    #[test]
+    fn wait_test() {
+        let x = Arc::new(NamedRegistry::<u32>::default());
+        assert_eq!(
+            x.wait_timeout("a", Duration::from_millis(1)),
+            Err(Error::Timeout)
+        );
+        let x2 = x.clone();
+        let handle = thread::spawn(move || *x2.wait("a"));
+        thread::sleep(Duration::from_millis(10));
+        x.register("b", 1).unwrap();
+        x.register("a", 2).unwrap();
+        assert_eq!(handle.join().unwrap(), 2);
+        assert_eq!(x.wait_timeout("b", Duration::from_millis(1)), Ok(&1));
+    }
+}
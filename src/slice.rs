@@ -0,0 +1,128 @@
+//! Helpers for slices of containers.
+
+use crate::{FreezeBox, MaybeBox};
+
+/// Extension methods for slices of [`FreezeBox`] or [`MaybeBox`].
+///
+/// This is useful for arrays of per-shard or per-CPU slots, which are
+/// initialized independently.
+///
+/// # Examples
+/// ```
+/// use freezebox::{FreezeBox, InitSliceExt};
+///
+/// let shards: Vec<FreezeBox<String>> = (0..4).map(|_| FreezeBox::default()).collect();
+/// assert_eq!(shards.first_uninitialized(), Some(0));
+///
+/// shards.init_all_from((0..4).map(|n| format!("shard-{}", n)));
+/// assert!(shards.all_initialized());
+/// assert_eq!(*shards[3], "shard-3");
+/// ```
+pub trait InitSliceExt {
+    /// The type stored in each container.
+    type Item;
+
+    /// Test whether every container is initialized.
+    ///
+    /// Returns true for an empty slice.
+    fn all_initialized(&self) -> bool;
+
+    /// Return the index of the first uninitialized container, if any.
+    fn first_uninitialized(&self) -> Option<usize>;
+
+    /// Initialize the containers in order, with values from `iter`.
+    ///
+    /// This stops when either the slice or the iterator runs out, and
+    /// returns the number of containers that were initialized.
+    ///
+    /// # Panics
+    ///
+    /// `init_all_from` will panic if it reaches a container that is already
+    /// initialized. Containers before that one will have been initialized.
+    fn init_all_from<I>(&self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = Self::Item>;
+}
+
+impl<T> InitSliceExt for [FreezeBox<T>] {
+    type Item = T;
+
+    fn all_initialized(&self) -> bool {
+        self.iter().all(FreezeBox::is_initialized)
+    }
+
+    fn first_uninitialized(&self) -> Option<usize> {
+        self.iter().position(|slot| !slot.is_initialized())
+    }
+
+    fn init_all_from<I>(&self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut count = 0;
+        for (slot, val) in self.iter().zip(iter) {
+            slot.lazy_init(val);
+            count += 1;
+        }
+        count
+    }
+}
+
+impl<T> InitSliceExt for [MaybeBox<T>] {
+    type Item = T;
+
+    fn all_initialized(&self) -> bool {
+        self.iter().all(MaybeBox::is_initialized)
+    }
+
+    fn first_uninitialized(&self) -> Option<usize> {
+        self.iter().position(|slot| !slot.is_initialized())
+    }
+
+    fn init_all_from<I>(&self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut count = 0;
+        for (slot, val) in self.iter().zip(iter) {
+            slot.lazy_init(val);
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InitSliceExt;
+    use crate::{FreezeBox, MaybeBox};
+
+    #[test]
+    fn freezebox_slice_test() {
+        let x: [FreezeBox<u32>; 3] = Default::default();
+        assert!(!x.all_initialized());
+        x[0].lazy_init(10);
+        assert_eq!(x.first_uninitialized(), Some(1));
+        assert_eq!(x[1..].init_all_from([11, 12, 13].iter().copied()), 2);
+        assert!(x.all_initialized());
+        assert_eq!(x.first_uninitialized(), None);
+        assert_eq!(*x[2], 12);
+    }
+
+    #[test]
+    fn maybebox_slice_test() {
+        let x: [MaybeBox<u32>; 3] = Default::default();
+        assert_eq!(x.init_all_from(0..2), 2);
+        assert_eq!(x.first_uninitialized(), Some(2));
+        assert!(!x.all_initialized());
+        assert!(<[MaybeBox<u32>]>::all_initialized(&[]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn init_all_from_initialized_test() {
+        let x: [FreezeBox<u32>; 2] = Default::default();
+        x[1].lazy_init(1);
+        x.init_all_from(0..2);
+    }
+}
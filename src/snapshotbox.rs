@@ -0,0 +1,234 @@
+//! This is the SnapshotBox implementation.
+
+use alloc::sync::{Arc, Weak};
+use core::ops::Deref;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A pinned version of the value in a [`SnapshotBox`].
+///
+/// A `Snapshot` keeps its version of the value alive, even if newer
+/// versions are published. When the last `Snapshot` of an old version is
+/// dropped, that version is reclaimed.
+///
+/// `Snapshot` implements `Deref`, so it can be used like a `&T`.
+pub struct Snapshot<T> {
+    value: Arc<T>,
+    version: u64,
+}
+
+impl<T> Snapshot<T> {
+    /// The version number of this snapshot.
+    ///
+    /// The first published value is version 1; each subsequent
+    /// publication increments the version by one.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get a `Weak` reference to this version of the value.
+    ///
+    /// Unlike a `Snapshot`, a `Weak` doesn't keep the version alive.
+    pub fn downgrade(&self) -> Weak<T> {
+        Arc::downgrade(&self.value)
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: Arc::clone(&self.value),
+            version: self.version,
+        }
+    }
+}
+
+impl<T> Deref for Snapshot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// `SnapshotBox` is a lazy-initialized container for versioned values.
+///
+/// A `SnapshotBox<T>` starts out uninitialized. Writers call `publish` to
+/// store a new version of the value, and readers call `pin` to get a
+/// [`Snapshot`] of the latest version.
+///
+/// A `Snapshot` never changes, even if newer versions are published later.
+/// This allows long-running operations to observe a consistent value from
+/// start to finish. Old versions are reclaimed once all of their snapshots
+/// are dropped.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::SnapshotBox;
+///
+/// let config = SnapshotBox::<String>::default();
+/// assert!(config.pin().is_none());
+/// config.publish("first".to_string());
+///
+/// // A request handler pins the current config.
+/// let pinned = config.pin().unwrap();
+///
+/// // The config changes while the request is in progress.
+/// config.publish("second".to_string());
+///
+/// // The request still sees the old config.
+/// assert_eq!(*pinned, "first");
+/// assert_eq!(*config.pin().unwrap(), "second");
+/// ```
+pub struct SnapshotBox<T> {
+    current: Mutex<Option<Snapshot<T>>>,
+}
+
+impl<T> SnapshotBox<T> {
+    /// Create a new `SnapshotBox` with optional initialization.
+    ///
+    /// To always create an uninitialized `SnapshotBox`, use
+    /// `SnapshotBox::default()`.
+    pub fn new(val: Option<T>) -> Self {
+        let sb = Self::default();
+        if let Some(v) = val {
+            sb.publish(v);
+        }
+        sb
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Snapshot<T>>> {
+        // The lock is never held while running user code, so poisoning
+        // can't leave the state inconsistent.
+        self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Publish a new version of the value.
+    ///
+    /// Subsequent calls to [`pin`][`SnapshotBox::pin`] will return the new
+    /// value. Existing snapshots are unaffected.
+    ///
+    /// Returns the version number of the new value.
+    pub fn publish(&self, val: T) -> u64 {
+        let value = Arc::new(val);
+        let mut current = self.lock();
+        let version = current.as_ref().map_or(0, |snapshot| snapshot.version) + 1;
+        let old = current.replace(Snapshot { value, version });
+        drop(current);
+
+        // If this was the last reference to the old version, it will be
+        // dropped here, after the lock is released.
+        drop(old);
+        version
+    }
+
+    /// Pin the latest version of the value.
+    ///
+    /// If a value has been published, this will return `Some(Snapshot<T>)`;
+    /// otherwise it will return None.
+    pub fn pin(&self) -> Option<Snapshot<T>> {
+        self.lock().clone()
+    }
+
+    /// Get a `Weak` reference to the latest version of the value.
+    ///
+    /// This allows an observer to look at the value without preventing it
+    /// from being reclaimed after a newer version is published. If no
+    /// value has been published, this will return None.
+    ///
+    /// # Examples
+    /// ```
+    /// use freezebox::SnapshotBox;
+    ///
+    /// let config = SnapshotBox::new(Some("first".to_string()));
+    /// let observer = config.downgrade().unwrap();
+    /// assert_eq!(*observer.upgrade().unwrap(), "first");
+    ///
+    /// config.publish("second".to_string());
+    /// assert!(observer.upgrade().is_none());
+    /// ```
+    pub fn downgrade(&self) -> Option<Weak<T>> {
+        self.lock().as_ref().map(Snapshot::downgrade)
+    }
+
+    /// Return the latest version number.
+    ///
+    /// If no value has been published, this will return 0.
+    pub fn version(&self) -> u64 {
+        self.lock().as_ref().map_or(0, |snapshot| snapshot.version)
+    }
+
+    /// Test whether a `SnapshotBox` is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.lock().is_some()
+    }
+}
+
+impl<T> Default for SnapshotBox<T> {
+    fn default() -> Self {
+        Self {
+            current: Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotBox;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn snapshotbox_test() {
+        let x = SnapshotBox::<String>::default();
+        assert!(!x.is_initialized());
+        assert_eq!(x.version(), 0);
+        assert!(x.pin().is_none());
+
+        assert_eq!(x.publish("first".to_string()), 1);
+        assert!(x.is_initialized());
+        let first = x.pin().unwrap();
+        assert_eq!(first.version(), 1);
+
+        assert_eq!(x.publish("second".to_string()), 2);
+        assert_eq!(x.version(), 2);
+        assert_eq!(*first, "first");
+        let second = x.pin().unwrap();
+        assert_eq!(*second, "second");
+        assert_eq!(second.version(), 2);
+    }
+
+    #[test]
+    fn reclaim_test() {
+        // Arc is used to check whether drop occurred.
+        let a = Arc::new(1u32);
+        let x = SnapshotBox::new(Some(a.clone()));
+        let pinned = x.pin().unwrap();
+        x.publish(Arc::new(2));
+
+        // The old version is kept alive by the snapshot.
+        assert_eq!(Arc::strong_count(&a), 2);
+        drop(pinned);
+        assert_eq!(Arc::strong_count(&a), 1);
+    }
+
+    #[test]
+    fn downgrade_test() {
+        let x = SnapshotBox::<u32>::default();
+        assert!(x.downgrade().is_none());
+        x.publish(1);
+        let pinned = x.pin().unwrap();
+        let weak1 = x.downgrade().unwrap();
+        let weak2 = pinned.downgrade();
+        x.publish(2);
+
+        // The old version is only kept alive by the snapshot.
+        assert_eq!(weak1.upgrade().map(|v| *v), Some(1));
+        drop(pinned);
+        assert!(weak1.upgrade().is_none());
+        assert!(weak2.upgrade().is_none());
+        assert_eq!(*x.downgrade().unwrap().upgrade().unwrap(), 2);
+    }
+}
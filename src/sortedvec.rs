@@ -0,0 +1,249 @@
+//! This is the FrozenSortedVec implementation.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `FrozenSortedVec` is a sorted collection of insert-once values.
+///
+/// Values can be inserted using only a shared reference, and references
+/// to inserted values remain valid for as long as the `FrozenSortedVec`
+/// lives, even as more values are inserted. Values can never be removed,
+/// except by consuming the collection with `into_vec()`.
+///
+/// The values are kept sorted, so lookups use a binary search. Each value
+/// is stored in its own heap allocation, which is what keeps references
+/// stable while the collection grows.
+///
+/// This type requires the `std` feature.
+///
+/// # Examples
+/// ```
+/// use freezebox::FrozenSortedVec;
+///
+/// let symbols = FrozenSortedVec::<String>::default();
+/// let foo: &String = symbols.insert("foo".to_string()).unwrap();
+/// symbols.insert("bar".to_string()).unwrap();
+///
+/// // Duplicate values are rejected.
+/// assert!(symbols.insert("foo".to_string()).is_err());
+///
+/// // `foo` is still valid, and lookups can use a borrowed key.
+/// assert_eq!(foo, "foo");
+/// assert_eq!(symbols.get("bar").unwrap(), "bar");
+/// assert_eq!(symbols.get_index(0).unwrap(), "bar");
+/// assert_eq!(symbols.into_vec(), ["bar", "foo"]);
+/// ```
+pub struct FrozenSortedVec<T> {
+    /// Pointers created by `Box::into_raw`, sorted by value.
+    ///
+    /// Raw pointers are used instead of `Box<T>`, because moving a `Box`
+    /// (as happens when the vector grows) asserts unique ownership, which
+    /// would invalidate the shared references we have handed out.
+    values: Mutex<Vec<*mut T>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> FrozenSortedVec<T> {
+    fn lock(&self) -> MutexGuard<'_, Vec<*mut T>> {
+        // The lock is only held while running comparisons, which can't
+        // leave the vector inconsistent even if they panic.
+        self.values.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get a reference to a value.
+    fn value_ref(&self, ptr: *mut T) -> &T {
+        // SAFETY: every pointer in the vector was created by
+        // `Box::into_raw`, and is only freed when `self` is consumed or
+        // dropped.
+        unsafe { &*ptr }
+    }
+
+    /// Search for a value using a comparison function.
+    ///
+    /// `f` should return the ordering of each value relative to the
+    /// target, like `slice::binary_search_by`. Returns a reference to a
+    /// matching value, if there is one.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Option<&T>
+    where
+        F: FnMut(&T) -> Ordering,
+    {
+        let values = self.lock();
+        match values.binary_search_by(|&v| f(self.value_ref(v))) {
+            Ok(index) => Some(self.value_ref(values[index])),
+            Err(_) => None,
+        }
+    }
+
+    /// Return the value at a given position in the sorted order.
+    ///
+    /// Note that inserting a value shifts the position of every greater
+    /// value.
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        let values = self.lock();
+        values.get(index).map(|&val| self.value_ref(val))
+    }
+
+    /// Return the number of values.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Test whether the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Consume the collection and return its values, in sorted order.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let values = self
+            .values
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Take the pointers, so that `Drop` doesn't free them again.
+        let values = core::mem::take(values);
+
+        // SAFETY: every pointer was created by `Box::into_raw`, and we
+        // have exclusive access.
+        values
+            .into_iter()
+            .map(|val| *unsafe { Box::from_raw(val) })
+            .collect()
+    }
+}
+
+impl<T: Ord> FrozenSortedVec<T> {
+    /// Insert a value.
+    ///
+    /// If an equal value is already present, the new value is returned
+    /// in `Err`. Otherwise, a reference to the inserted value is returned.
+    pub fn insert(&self, val: T) -> Result<&T, T> {
+        let mut values = self.lock();
+        match values.binary_search_by(|&v| self.value_ref(v).cmp(&val)) {
+            Ok(_) => Err(val),
+            Err(index) => {
+                let ptr = Box::into_raw(Box::new(val));
+                values.insert(index, ptr);
+                Ok(self.value_ref(ptr))
+            }
+        }
+    }
+
+    /// Look up a value.
+    ///
+    /// The key may be any borrowed form of the value type, like
+    /// `&str` for a `FrozenSortedVec<String>`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.binary_search_by(|v| v.borrow().cmp(key))
+    }
+
+    /// Test whether a value is present.
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<T> Default for FrozenSortedVec<T> {
+    fn default() -> Self {
+        Self {
+            values: Mutex::new(Vec::new()),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for FrozenSortedVec<T> {
+    fn drop(&mut self) {
+        let values = self
+            .values
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for &val in values.iter() {
+            // SAFETY: every pointer was created by `Box::into_raw`, and we
+            // have exclusive access.
+            let _owned = unsafe { Box::from_raw(val) };
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `FrozenSortedVec` owns its values, like a `Vec<Box<T>>`.
+unsafe impl<T: Send> Send for FrozenSortedVec<T> {}
+
+// SAFETY: shared references to the values are handed out to any thread,
+// and a value inserted by one thread may be dropped by another.
+unsafe impl<T: Send + Sync> Sync for FrozenSortedVec<T> {}
+
+/// Must fail to compile because FrozenSortedVec<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::FrozenSortedVec;
+/// use std::cell::Cell;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = FrozenSortedVec::<Cell<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenSortedVec;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::thread;
+
+    #[test]
+    fn sortedvec_test() {
+        let x = FrozenSortedVec::<String>::default();
+        assert!(x.is_empty());
+        let c = x.insert("c".to_string()).unwrap();
+        let a = x.insert("a".to_string()).unwrap();
+        let b = x.insert("b".to_string()).unwrap();
+        assert_eq!(x.insert("b".to_string()), Err("b".to_string()));
+
+        // References survive later inserts.
+        assert_eq!((a.as_str(), b.as_str(), c.as_str()), ("a", "b", "c"));
+        assert_eq!(x.len(), 3);
+        assert!(x.contains("a"));
+        assert!(!x.contains("d"));
+        assert_eq!(x.get_index(1).unwrap(), "b");
+        assert!(x.get_index(3).is_none());
+        assert_eq!(x.binary_search_by(|v| v.as_str().cmp("c")).unwrap(), "c");
+        assert_eq!(x.into_vec(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn concurrent_test() {
+        let x = Arc::new(FrozenSortedVec::<u32>::default());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let x = x.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let _ = x.insert(i * 4 + t);
+                        let _ = x.insert(i);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let x = Arc::try_unwrap(x).ok().unwrap();
+        assert_eq!(x.into_vec(), (0..400).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,121 @@
+//! Initializing containers on a background thread.
+
+use crate::{FreezeBox, MaybeBox};
+use alloc::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+impl<T: Send + Sync + 'static> FreezeBox<T> {
+    /// Initialize a `static` `FreezeBox` on a background thread.
+    ///
+    /// `f` runs on a newly spawned thread, and its result is used to
+    /// initialize the `FreezeBox`. This allows slow initialization to
+    /// proceed without blocking startup. Join the returned handle to wait
+    /// for initialization to complete.
+    ///
+    /// If `f` panics, or the `FreezeBox` was already initialized, the
+    /// panic is returned by [`JoinHandle::join`].
+    ///
+    /// This method requires the `std` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// # use freezebox::FreezeBox;
+    /// static TABLE: FreezeBox<Vec<u32>> = FreezeBox::const_default();
+    ///
+    /// let handle = TABLE.spawn_init(|| (0..100).collect());
+    /// // ... other startup work ...
+    /// handle.join().unwrap();
+    /// assert_eq!(TABLE.len(), 100);
+    /// ```
+    pub fn spawn_init<F>(&'static self, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        thread::spawn(move || {
+            self.lazy_init(f());
+        })
+    }
+
+    /// Initialize a shared `FreezeBox` on a background thread.
+    ///
+    /// This is the same as [`spawn_init`][FreezeBox::spawn_init], for a
+    /// `FreezeBox` that is shared with an `Arc`.
+    pub fn spawn_init_arc<F>(self: &Arc<Self>, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let this = Arc::clone(self);
+        thread::spawn(move || {
+            this.lazy_init(f());
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> MaybeBox<T> {
+    /// Initialize a `static` `MaybeBox` on a background thread.
+    ///
+    /// `f` runs on a newly spawned thread, and its result is used to
+    /// initialize the `MaybeBox`. Until it completes, `get` will return
+    /// `None`. Join the returned handle to wait for initialization to
+    /// complete.
+    ///
+    /// If `f` panics, or the `MaybeBox` was already initialized, the
+    /// panic is returned by [`JoinHandle::join`].
+    ///
+    /// This method requires the `std` feature.
+    pub fn spawn_init<F>(&'static self, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        thread::spawn(move || {
+            self.lazy_init(f());
+        })
+    }
+
+    /// Initialize a shared `MaybeBox` on a background thread.
+    ///
+    /// This is the same as [`spawn_init`][MaybeBox::spawn_init], for a
+    /// `MaybeBox` that is shared with an `Arc`.
+    pub fn spawn_init_arc<F>(self: &Arc<Self>, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let this = Arc::clone(self);
+        thread::spawn(move || {
+            this.lazy_init(f());
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FreezeBox, MaybeBox};
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn spawn_init_test() {
+        static X: FreezeBox<String> = FreezeBox::const_default();
+        static Y: MaybeBox<String> = MaybeBox::const_default();
+        let hx = X.spawn_init(|| "hello".to_string());
+        let hy = Y.spawn_init(|| "world".to_string());
+        hx.join().unwrap();
+        hy.join().unwrap();
+        assert_eq!(*X, "hello");
+        assert_eq!(Y.get().unwrap(), "world");
+    }
+
+    #[test]
+    fn spawn_init_arc_test() {
+        let x = Arc::new(FreezeBox::<String>::default());
+        let y = Arc::new(MaybeBox::<String>::default());
+        x.spawn_init_arc(|| "hello".to_string()).join().unwrap();
+        y.spawn_init_arc(|| "world".to_string()).join().unwrap();
+        assert_eq!(**x, "hello");
+        assert_eq!(y.get().unwrap(), "world");
+
+        // A second initialization panics on the background thread.
+        assert!(x.spawn_init_arc(String::new).join().is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! This is the StackFreeze implementation.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ops::Deref;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// `StackFreeze` is a lazy-initialized container that stores its value in
+/// caller-provided memory.
+///
+/// It behaves like a [`FreezeBox`][crate::FreezeBox], but instead of
+/// allocating a `Box`, the value is written into a `MaybeUninit<T>` slot
+/// that the caller supplies. The slot may live on the stack, in a
+/// `static`, or in an arena, so `StackFreeze` can be used without a heap.
+///
+/// The slot stays borrowed for as long as the `StackFreeze` lives. When
+/// the `StackFreeze` is dropped, the value is dropped too, and the slot is
+/// uninitialized again.
+///
+/// # Examples
+/// ```
+/// use freezebox::StackFreeze;
+/// use std::mem::MaybeUninit;
+///
+/// let mut slot = MaybeUninit::uninit();
+/// let x = StackFreeze::<String>::new(&mut slot);
+/// assert!(!x.is_initialized());
+///
+/// x.lazy_init("hello".to_string());
+/// assert_eq!(*x, "hello");
+/// ```
+pub struct StackFreeze<'a, T> {
+    /// The caller's slot.
+    slot: *mut T,
+    /// Set by the thread that writes the value into the slot.
+    claimed: AtomicBool,
+    /// Null until the value is published, then equal to `slot`.
+    inner: AtomicPtr<T>,
+    phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> StackFreeze<'a, T> {
+    /// Create an uninitialized `StackFreeze` that will store its value in
+    /// `slot`.
+    pub fn new(slot: &'a mut MaybeUninit<T>) -> Self {
+        Self {
+            slot: slot.as_mut_ptr(),
+            claimed: AtomicBool::new(false),
+            inner: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Initialize a `StackFreeze`.
+    ///
+    /// The new value will be written into the caller's slot.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the `StackFreeze` is already initialized.
+    /// If it panics, the input value will be dropped.
+    pub fn lazy_init(&self, val: T) {
+        // Only one thread may write to the slot, so claim it first. The
+        // value isn't visible to readers until it is published below.
+        if self
+            .claimed
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            drop(val);
+            panic_with_type!("lazy_init on already-initialized StackFreeze", T);
+        }
+
+        // SAFETY: `slot` was created from a `&'a mut MaybeUninit<T>`, and
+        // we won the claim, so nobody else is accessing it.
+        unsafe { self.slot.write(val) };
+
+        // `Release` ordering ensures that readers who see the pointer will
+        // also see the value written above.
+        crate::chaos::delay();
+        self.inner.store(self.slot, Ordering::Release);
+    }
+
+    /// Try to get a reference to the value in the `StackFreeze`.
+    ///
+    /// Returns `None` if the `StackFreeze` is uninitialized.
+    pub fn get(&self) -> Option<&T> {
+        let ptr = self.inner.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null, or points to the caller's
+        // slot after a value was written to it. The value can't be
+        // removed except by consuming `self`.
+        unsafe { ptr.as_ref() }
+    }
+
+    /// Test whether a `StackFreeze` is initialized.
+    pub fn is_initialized(&self) -> bool {
+        !self.inner.load(Ordering::Acquire).is_null()
+    }
+
+    /// Consume the `StackFreeze` and return its contents.
+    ///
+    /// The caller's slot is left uninitialized.
+    pub fn into_inner(mut self) -> Option<T> {
+        let ptr = *self.inner.get_mut();
+        mem::forget(self);
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: the value was written to the slot by `lazy_init`, and
+        // because we are consuming `self`, nobody else can access it. We
+        // forgot `self`, so the value won't be dropped twice.
+        Some(unsafe { ptr.read() })
+    }
+}
+
+impl<T> Deref for StackFreeze<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self.get() {
+            Some(val) => val,
+            None => panic_with_type!("attempted to deref uninitialized StackFreeze", T),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StackFreeze<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(val) => f.debug_tuple("StackFreeze").field(val).finish(),
+            None => f.write_str("StackFreeze(<uninitialized>)"),
+        }
+    }
+}
+
+impl<T> Drop for StackFreeze<'_, T> {
+    fn drop(&mut self) {
+        let ptr = *self.inner.get_mut();
+        if !ptr.is_null() {
+            // SAFETY: the value was written to the slot by `lazy_init`, and
+            // we have exclusive access.
+            unsafe { ptr::drop_in_place(ptr) };
+        }
+    }
+}
+
+// SAFETY: a `StackFreeze` owns its value, like a `FreezeBox`.
+unsafe impl<T: Send> Send for StackFreeze<'_, T> {}
+
+// SAFETY: shared references to the value are handed out to any thread,
+// and a value written by one thread may be dropped by another.
+unsafe impl<T: Send + Sync> Sync for StackFreeze<'_, T> {}
+
+/// Must fail to compile because StackFreeze<Cell> must not be Sync.
+/// ```compile_fail
+/// use freezebox::StackFreeze;
+/// use std::cell::Cell;
+/// use std::mem::MaybeUninit;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let mut slot = MaybeUninit::uninit();
+/// let x = StackFreeze::<Cell<u32>>::new(&mut slot);
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::StackFreeze;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use core::mem::MaybeUninit;
+
+    #[test]
+    fn stackfreeze_test() {
+        let mut slot = MaybeUninit::uninit();
+        let x = StackFreeze::<String>::new(&mut slot);
+        assert_eq!(x.get(), None);
+        assert_eq!(format!("{:?}", x), "StackFreeze(<uninitialized>)");
+        x.lazy_init("hello".to_string());
+        assert_eq!(x.get().unwrap(), "hello");
+        assert_eq!(x.len(), 5);
+        assert_eq!(format!("{:?}", x), "StackFreeze(\"hello\")");
+        assert_eq!(x.into_inner().unwrap(), "hello");
+    }
+
+    #[test]
+    fn drop_test() {
+        let value = Arc::new(());
+        let mut slot = MaybeUninit::uninit();
+        let x = StackFreeze::<Arc<()>>::new(&mut slot);
+        x.lazy_init(value.clone());
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(x);
+        assert_eq!(Arc::strong_count(&value), 1);
+
+        // The slot can be reused.
+        let y = StackFreeze::new(&mut slot);
+        assert!(y.into_inner().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "lazy_init on already-initialized StackFreeze")]
+    fn double_init_test() {
+        let mut slot = MaybeUninit::uninit();
+        let x = StackFreeze::<u32>::new(&mut slot);
+        x.lazy_init(1);
+        x.lazy_init(2);
+    }
+}
@@ -0,0 +1,405 @@
+//! This is the TeardownBox implementation.
+
+#[cfg(feature = "std")]
+use crate::waiter::Waiters;
+#[cfg(feature = "std")]
+use crate::MaybeBox;
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::{self, null_mut};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// The high bit of `TeardownBox::state` marks the container as sealed.
+const SEALED: usize = !(usize::MAX >> 1);
+
+/// `TeardownBox` is a lazy-initialized container that can be torn down.
+///
+/// A `TeardownBox<T>` has three possible states:
+/// * uninitialized: [`get`] will return `None`.
+/// * initialized: [`get`] will return a guard that derefs to `&T`.
+/// * sealed: the contents have been dropped by [`teardown`], and [`get`]
+///   will return `None` forever.
+///
+/// Static singletons are never dropped, so anything they own (files,
+/// sockets) is never released. A `TeardownBox` allows the owner of its
+/// [`TeardownKey`] to drop the contents during shutdown. Readers access
+/// the contents through a [`TeardownGuard`], and `teardown` waits for all
+/// guards to be released before dropping the contents, so no reader can
+/// observe a dropped value.
+///
+/// # Panics
+///
+/// Attempting to `lazy_init` more than once, or after the `TeardownBox`
+/// is sealed, will cause a panic.
+///
+/// [`get`]: TeardownBox::get
+/// [`teardown`]: TeardownBox::teardown
+///
+/// # Examples
+/// ```
+/// use freezebox::TeardownBox;
+///
+/// static LOG: TeardownBox<String> = TeardownBox::const_default();
+///
+/// // Startup code takes the key, and initializes the container.
+/// let key = LOG.teardown_key().unwrap();
+/// LOG.lazy_init("log file".to_string());
+/// assert_eq!(*LOG.get().unwrap(), "log file");
+///
+/// // Nobody else can get the key.
+/// assert!(LOG.teardown_key().is_none());
+///
+/// // At shutdown, the contents are dropped.
+/// LOG.teardown(key);
+/// assert!(LOG.get().is_none());
+/// assert!(LOG.is_sealed());
+/// ```
+pub struct TeardownBox<T> {
+    inner: AtomicPtr<T>,
+    /// The number of active readers, plus the `SEALED` bit.
+    state: AtomicUsize,
+    key_taken: AtomicBool,
+    /// Wakes `teardown` when the last reader exits.
+    ///
+    /// `Waiters` can't be created in const context, so it is allocated
+    /// by `teardown`.
+    #[cfg(feature = "std")]
+    waiters: MaybeBox<Waiters>,
+    phantom: PhantomData<T>,
+}
+
+/// The capability to tear down a [`TeardownBox`].
+///
+/// Only one `TeardownKey` is ever created for each `TeardownBox`; see
+/// [`TeardownBox::teardown_key`].
+pub struct TeardownKey<'a, T> {
+    target: &'a TeardownBox<T>,
+}
+
+/// A reference to the contents of a [`TeardownBox`].
+///
+/// While any `TeardownGuard` exists, [`TeardownBox::teardown`] will wait
+/// rather than drop the contents.
+pub struct TeardownGuard<'a, T> {
+    target: &'a TeardownBox<T>,
+    value: &'a T,
+}
+
+impl<T> TeardownBox<T> {
+    /// Create a new `TeardownBox` in `const` context
+    ///
+    /// This is the same as `TeardownBox::default` except that it works in
+    /// const context, which is desirable for global `static` singleton
+    /// objects.
+    pub const fn const_default() -> Self {
+        Self {
+            inner: AtomicPtr::new(null_mut()),
+            state: AtomicUsize::new(0),
+            key_taken: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            waiters: MaybeBox::const_default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Take the `TeardownKey` for this container.
+    ///
+    /// This will return `Some` the first time it is called, and `None`
+    /// every time after that.
+    pub fn teardown_key(&self) -> Option<TeardownKey<'_, T>> {
+        if self.key_taken.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(TeardownKey { target: self })
+        }
+    }
+
+    /// Register as an active reader, unless the container is sealed.
+    fn enter(&self) -> bool {
+        // `Acquire` ordering pairs with the `Release` in `teardown`, and
+        // ensures that if we see the container unsealed, `teardown` will
+        // see our reader count.
+        if self.state.fetch_add(1, Ordering::AcqRel) & SEALED != 0 {
+            self.exit();
+            return false;
+        }
+        true
+    }
+
+    /// Unregister as an active reader.
+    fn exit(&self) {
+        // `Acquire` ordering ensures that if we see the `SEALED` bit, we
+        // also see the waiters that `teardown` created before setting it.
+        if self.state.fetch_sub(1, Ordering::AcqRel) == SEALED | 1 {
+            // We were the last reader, and `teardown` is waiting.
+            self.wake_teardown();
+        }
+    }
+
+    /// Wake the thread waiting in `teardown`.
+    fn wake_teardown(&self) {
+        #[cfg(feature = "std")]
+        {
+            if let Some(waiters) = self.waiters.get() {
+                waiters.notify_all();
+            }
+        }
+    }
+
+    /// Initialize a `TeardownBox`.
+    ///
+    /// The new value will be stored on the heap.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the `TeardownBox` is already initialized,
+    /// or if it has been sealed. If it panics, the input value will be
+    /// dropped.
+    pub fn lazy_init(&self, val: T) {
+        if !self.enter() {
+            panic_with_type!("lazy_init on sealed TeardownBox", T);
+        }
+        let ptr = Box::into_raw(Box::new(val));
+        crate::observer::observe_alloc::<T>();
+
+        // The atomic ordering here is the same as `FreezeBox::lazy_init`.
+        crate::chaos::delay();
+        let result =
+            self.inner
+                .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire);
+        self.exit();
+
+        if result.is_err() {
+            // SAFETY: `ptr` was just created above using `Box::into_raw`,
+            // and because compare_exchange failed, we are still its unique
+            // owner.
+            let _val = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+
+            panic_with_type!("lazy_init on already-initialized TeardownBox", T);
+        }
+    }
+
+    /// Try to get a reference to the data in the `TeardownBox`.
+    ///
+    /// If the `TeardownBox` is initialized and not sealed, this will return
+    /// a guard that derefs to `&T`; otherwise it will return None.
+    pub fn get(&self) -> Option<TeardownGuard<'_, T>> {
+        if !self.enter() {
+            return None;
+        }
+        let ptr = self.inner.load(Ordering::Acquire);
+
+        // SAFETY: the pointer is either null, or was created by
+        // `Box::into_raw` in `lazy_init`. Because we are registered as a
+        // reader, `teardown` can't free it until the guard is dropped.
+        match unsafe { ptr.as_ref() } {
+            Some(value) => Some(TeardownGuard {
+                target: self,
+                value,
+            }),
+            None => {
+                self.exit();
+                None
+            }
+        }
+    }
+
+    /// Test whether a `TeardownBox` is initialized.
+    ///
+    /// A sealed `TeardownBox` is not initialized.
+    pub fn is_initialized(&self) -> bool {
+        !self.is_sealed() && !self.inner.load(Ordering::Acquire).is_null()
+    }
+
+    /// Test whether a `TeardownBox` has been sealed by `teardown`.
+    pub fn is_sealed(&self) -> bool {
+        self.state.load(Ordering::Acquire) & SEALED != 0
+    }
+
+    /// Seal the `TeardownBox` and drop its contents.
+    ///
+    /// After `teardown` starts, no new guards can be created. `teardown`
+    /// then waits for all existing guards to be dropped before dropping
+    /// the contents.
+    ///
+    /// # Panics
+    ///
+    /// `teardown` will panic if `key` belongs to a different `TeardownBox`.
+    ///
+    /// Calling `teardown` while the current thread holds a guard from the
+    /// same container will deadlock.
+    ///
+    /// With the `std` feature, `teardown` parks the thread while it waits
+    /// for the last guard to be dropped. Without it, `teardown` spins, so
+    /// a guard that is held for a long time will keep a core busy.
+    pub fn teardown(&self, key: TeardownKey<'_, T>) {
+        if !ptr::eq(key.target, self) {
+            panic_with_type!("teardown with the key of a different TeardownBox", T);
+        }
+
+        // The waiters must exist before the container is sealed, so that
+        // the last reader to exit can find them.
+        #[cfg(feature = "std")]
+        let waiters = self.waiters.get_or_init(Waiters::default);
+
+        self.state.fetch_or(SEALED, Ordering::AcqRel);
+        let no_readers = || self.state.load(Ordering::Acquire) & !SEALED == 0;
+
+        #[cfg(feature = "std")]
+        waiters.wait(no_readers);
+
+        #[cfg(not(feature = "std"))]
+        while !no_readers() {
+            #[allow(deprecated)]
+            core::sync::atomic::spin_loop_hint();
+        }
+
+        let ptr = self.inner.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !ptr.is_null() {
+            // SAFETY: the container is sealed and there are no readers, so
+            // no references to the contents exist. `ptr` was created by
+            // `Box::into_raw` in `lazy_init`.
+            let _owned = unsafe { Box::<T>::from_raw(ptr) };
+            crate::observer::observe_free::<T>();
+            // _owned will drop here.
+        }
+    }
+}
+
+impl<T> Default for TeardownBox<T> {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+impl<T> Drop for TeardownBox<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+
+        if !inner.is_null() {
+            // SAFETY: We have exclusive access to the inner value, which
+            // was created by `Box::into_raw` in `lazy_init`.
+            let _owned = unsafe { Box::<T>::from_raw(*inner) };
+            crate::observer::observe_free::<T>();
+            // _owned will drop here.
+        }
+    }
+}
+
+// SAFETY: `TeardownBox` owns its contents like a `Box<T>`, and hands out
+// shared references to them, so it's safe to send or share as long as
+// `T` is.
+unsafe impl<T: Send> Send for TeardownBox<T> {}
+unsafe impl<T: Send + Sync> Sync for TeardownBox<T> {}
+
+impl<T> Deref for TeardownGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> Drop for TeardownGuard<'_, T> {
+    fn drop(&mut self) {
+        self.target.exit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TeardownBox;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn teardown_test() {
+        // Arc is used to check whether drop occurred.
+        let x = Arc::new("hello".to_string());
+        let y: TeardownBox<Arc<String>> = TeardownBox::default();
+        let key = y.teardown_key().unwrap();
+        assert!(y.teardown_key().is_none());
+        assert!(!y.is_initialized());
+        assert!(y.get().is_none());
+
+        y.lazy_init(x.clone());
+        assert!(y.is_initialized());
+        assert_eq!(**y.get().unwrap(), "hello");
+
+        assert_eq!(Arc::strong_count(&x), 2);
+        y.teardown(key);
+        assert_eq!(Arc::strong_count(&x), 1);
+        assert!(y.is_sealed());
+        assert!(!y.is_initialized());
+        assert!(y.get().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_init_after_teardown() {
+        let x = TeardownBox::<String>::default();
+        x.teardown(x.teardown_key().unwrap());
+        x.lazy_init("hello".to_string());
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_wrong_key() {
+        let x = TeardownBox::<String>::default();
+        let y = TeardownBox::<String>::default();
+        x.teardown(y.teardown_key().unwrap());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn teardown_waits_for_readers() {
+        use core::time::Duration;
+        use std::thread;
+
+        let x = Arc::new(TeardownBox::<String>::default());
+        x.lazy_init("hello".to_string());
+        let guard = x.get().unwrap();
+
+        let x2 = x.clone();
+        let handle = thread::spawn(move || x2.teardown(x2.teardown_key().unwrap()));
+
+        // The teardown can't complete while we hold a guard.
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(*guard, "hello");
+        assert!(x.get().is_none());
+        drop(guard);
+        handle.join().unwrap();
+        assert!(x.is_sealed());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn teardown_with_busy_readers() {
+        use alloc::vec::Vec;
+        use std::thread;
+
+        for _ in 0..20 {
+            let x = Arc::new(TeardownBox::<u32>::default());
+            let key = x.teardown_key().unwrap();
+            x.lazy_init(1);
+            let readers: Vec<_> = (0..4)
+                .map(|_| {
+                    let x = x.clone();
+                    thread::spawn(move || {
+                        while let Some(guard) = x.get() {
+                            assert_eq!(*guard, 1);
+                        }
+                    })
+                })
+                .collect();
+            // The last reader to drop its guard must wake the teardown.
+            x.teardown(key);
+            for r in readers {
+                r.join().unwrap();
+            }
+        }
+    }
+}
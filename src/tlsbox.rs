@@ -0,0 +1,291 @@
+//! This is the TlsBox implementation.
+
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::thread::{self, ThreadId};
+
+/// One thread's value, stored in a singly-linked list.
+struct Entry<T> {
+    owner: ThreadId,
+    value: T,
+    next: *mut Entry<T>,
+}
+
+/// `TlsBox` is a container holding one lazy-initialized value per thread.
+///
+/// Unlike `thread_local!`, a `TlsBox` is an ordinary value, so it can be
+/// used as a struct member. Each thread that accesses the `TlsBox` sees
+/// only its own value, which that thread must initialize.
+///
+/// Values are not dropped when their thread exits; they live as long as
+/// the `TlsBox`.
+///
+/// This type requires the `std` feature.
+///
+/// # Panics
+///
+/// Attempting to `lazy_init` more than once from the same thread will
+/// cause a panic.
+///
+/// # Examples
+/// ```
+/// use freezebox::TlsBox;
+/// use std::cell::RefCell;
+/// use std::sync::Arc;
+///
+/// /// A shared engine with a per-thread scratch buffer.
+/// #[derive(Default)]
+/// struct Engine {
+///     scratch: TlsBox<RefCell<Vec<u8>>>,
+/// }
+///
+/// let engine = Arc::new(Engine::default());
+/// let engine2 = engine.clone();
+///
+/// std::thread::spawn(move || {
+///     let scratch = engine2.scratch.get_or(Default::default);
+///     scratch.borrow_mut().push(1);
+///     assert_eq!(scratch.borrow().len(), 1);
+/// })
+/// .join()
+/// .unwrap();
+///
+/// // This thread has a separate, empty buffer.
+/// let scratch = engine.scratch.get_or(Default::default);
+/// assert!(scratch.borrow().is_empty());
+/// ```
+pub struct TlsBox<T> {
+    head: AtomicPtr<Entry<T>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> TlsBox<T> {
+    /// Create a new `TlsBox` in `const` context
+    ///
+    /// This is the same as `TlsBox::default` except that it works in
+    /// const context, which is desirable for global `static` objects.
+    pub const fn const_default() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Find the entry belonging to the current thread, if any.
+    fn find(&self) -> Option<&T> {
+        let owner = thread::current().id();
+        let mut cur = self.head.load(Ordering::Acquire);
+
+        // SAFETY: every non-null pointer in the list was created by
+        // `Box::into_raw` and published with `Release` ordering. Entries are
+        // never removed while the `TlsBox` is shared, so they remain valid
+        // for as long as `self` lives.
+        while let Some(entry) = unsafe { cur.as_ref() } {
+            if entry.owner == owner {
+                return Some(&entry.value);
+            }
+            cur = entry.next;
+        }
+        None
+    }
+
+    /// Add a new entry for the current thread.
+    ///
+    /// The caller must ensure that the current thread has no entry yet.
+    fn insert(&self, value: T) -> &T {
+        let entry = Box::into_raw(Box::new(Entry {
+            owner: thread::current().id(),
+            value,
+            next: ptr::null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // SAFETY: `entry` is not yet published, so we are its unique owner.
+            unsafe { (*entry).next = head };
+
+            // Other threads may be pushing their own entries at the same
+            // time, so retry until our entry is at the head of the list.
+            // `Release` publishes the entry contents; `Acquire` on failure
+            // lets us safely link to the new head.
+            match self
+                .head
+                .compare_exchange_weak(head, entry, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(new_head) => head = new_head,
+            }
+        }
+
+        // SAFETY: the entry is now owned by the list, which never frees
+        // entries while the `TlsBox` is shared.
+        unsafe { &(*entry).value }
+    }
+
+    /// Initialize the current thread's value.
+    ///
+    /// The new value will be stored on the heap.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the current thread's value is already
+    /// initialized. If it panics, the input value will be dropped.
+    pub fn lazy_init(&self, val: T) {
+        if self.find().is_some() {
+            panic_with_type!("lazy_init on already-initialized TlsBox", T);
+        }
+        self.insert(val);
+    }
+
+    /// Try to get a reference to the current thread's value.
+    ///
+    /// If the current thread has initialized its value, this will return
+    /// `Some(&T)`; otherwise it will return None.
+    pub fn get(&self) -> Option<&T> {
+        self.find()
+    }
+
+    /// Get the current thread's value, initializing it if necessary.
+    ///
+    /// If the current thread's value is uninitialized, `f` is called to
+    /// create it. If `f` panics, the value remains uninitialized.
+    pub fn get_or<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.find() {
+            Some(val) => val,
+            None => self.insert(f()),
+        }
+    }
+
+    /// Test whether the current thread's value is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.find().is_some()
+    }
+
+    /// Visit the values belonging to every thread.
+    ///
+    /// Because this requires exclusive access, no other thread can be
+    /// using its value at the same time.
+    pub fn for_each_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T),
+    {
+        let mut cur = *self.head.get_mut();
+
+        // SAFETY: we have exclusive access, and every non-null pointer in
+        // the list was created by `Box::into_raw`.
+        while let Some(entry) = unsafe { cur.as_mut() } {
+            f(&mut entry.value);
+            cur = entry.next;
+        }
+    }
+}
+
+impl<T> Default for TlsBox<T> {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+impl<T> Drop for TlsBox<T> {
+    fn drop(&mut self) {
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            // SAFETY: We have exclusive access to the list, and every entry
+            // was created by `Box::into_raw`. Each entry is visited once.
+            let entry = unsafe { Box::from_raw(cur) };
+            cur = entry.next;
+            // entry will drop here.
+        }
+    }
+}
+
+// SAFETY: A `TlsBox` owns its values, so sending it to another thread sends
+// every value; this requires `T: Send`.
+unsafe impl<T: Send> Send for TlsBox<T> {}
+
+// SAFETY: A shared `TlsBox` only hands out a `&T` to the thread that created
+// that value, so `T` never needs to be `Sync`. Values may be dropped (or
+// visited with `for_each_mut`) from a different thread than the one that
+// created them, so `T: Send` is required.
+unsafe impl<T: Send> Sync for TlsBox<T> {}
+
+/// Must fail to compile because TlsBox<Rc> must not be Sync.
+/// ```compile_fail
+/// use freezebox::TlsBox;
+/// use std::rc::Rc;
+///
+/// fn require_sync<T: Sync>(_t: &T) {}
+///
+/// let x = TlsBox::<Rc<u32>>::default();
+/// require_sync(&x); // must fail to compile.
+/// ```
+struct _Unused; // Only exists to get the compile-fail doctest
+
+#[cfg(test)]
+mod tests {
+    use super::TlsBox;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn tlsbox_test() {
+        // Arc is used to check whether drop occurred.
+        let x = Arc::new("hello".to_string());
+        let y: Arc<TlsBox<Arc<String>>> = Arc::default();
+        assert!(!y.is_initialized());
+        assert!(y.get().is_none());
+        y.lazy_init(x.clone());
+        assert!(y.is_initialized());
+        assert_eq!(**y.get().unwrap(), "hello");
+
+        let handles: alloc::vec::Vec<_> = (0..4)
+            .map(|_| {
+                let x = x.clone();
+                let y = y.clone();
+                thread::spawn(move || {
+                    assert!(y.get().is_none());
+                    let val = y.get_or(|| x.clone());
+                    assert!(Arc::ptr_eq(val, &x));
+                    assert!(Arc::ptr_eq(y.get_or(|| unreachable!()), &x));
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        // Values outlive their threads, and are dropped with the TlsBox.
+        assert_eq!(Arc::strong_count(&x), 6);
+        drop(y);
+        assert_eq!(Arc::strong_count(&x), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_double_init() {
+        let x = TlsBox::<String>::default();
+        x.lazy_init("first".to_string());
+        x.lazy_init("second".to_string());
+    }
+
+    #[test]
+    fn for_each_test() {
+        let x = TlsBox::<u32>::default();
+        x.lazy_init(1);
+        let x = Arc::new(x);
+        let x2 = x.clone();
+        thread::spawn(move || x2.lazy_init(2)).join().unwrap();
+
+        let mut x = Arc::try_unwrap(x).ok().unwrap();
+        let mut sum = 0;
+        x.for_each_mut(|val| sum += *val);
+        assert_eq!(sum, 3);
+    }
+}
@@ -0,0 +1,224 @@
+//! Shared blocking and async waiting infrastructure.
+
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The default number of times to check the condition before blocking.
+pub(crate) const DEFAULT_SPIN_LIMIT: u32 = 100;
+
+/// A list of blocked threads and async tasks waiting for a condition.
+///
+/// The condition itself is stored elsewhere (usually in an atomic). To
+/// avoid missed wakeups, the condition must be made true *before* calling
+/// [`notify_all`][`Waiters::notify_all`].
+///
+/// Blocking waits spin briefly before parking the thread, because the
+/// condition often becomes true within microseconds, and parking adds
+/// significant latency.
+pub(crate) struct Waiters {
+    wakers: Mutex<Vec<Waker>>,
+    condvar: Condvar,
+    spin_limit: u32,
+}
+
+impl Default for Waiters {
+    fn default() -> Self {
+        Self {
+            wakers: Mutex::default(),
+            condvar: Condvar::new(),
+            spin_limit: DEFAULT_SPIN_LIMIT,
+        }
+    }
+}
+
+impl Waiters {
+    /// Set the number of times to check the condition before blocking.
+    pub(crate) fn set_spin_limit(&mut self, spin_limit: u32) {
+        self.spin_limit = spin_limit;
+    }
+
+    /// Spin until `ready` returns true, up to the spin limit.
+    ///
+    /// Returns the final result of `ready`.
+    fn spin<F>(&self, ready: &F) -> bool
+    where
+        F: Fn() -> bool,
+    {
+        for _ in 0..self.spin_limit {
+            if ready() {
+                return true;
+            }
+            #[allow(deprecated)]
+            core::sync::atomic::spin_loop_hint();
+        }
+        ready()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Waker>> {
+        // The lock is never held while running user code, so poisoning
+        // can't leave the list inconsistent.
+        self.wakers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Block the current thread until `ready` returns true.
+    pub(crate) fn wait<F>(&self, ready: F)
+    where
+        F: Fn() -> bool,
+    {
+        if self.spin(&ready) {
+            return;
+        }
+        let mut guard = self.lock();
+        while !ready() {
+            if crate::chaos::spurious_wakeup() {
+                guard = self
+                    .condvar
+                    .wait_timeout(guard, Duration::from_micros(10))
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+                continue;
+            }
+            guard = self
+                .condvar
+                .wait(guard)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Block the current thread until `ready` returns true, or until
+    /// `timeout` has elapsed.
+    ///
+    /// Returns the final result of `ready`.
+    pub(crate) fn wait_timeout<F>(&self, ready: F, timeout: Duration) -> bool
+    where
+        F: Fn() -> bool,
+    {
+        if self.spin(&ready) {
+            return true;
+        }
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.lock();
+        while !ready() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            guard = self
+                .condvar
+                .wait_timeout(guard, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        true
+    }
+
+    /// Poll for the condition, registering the task's waker if it is
+    /// not yet ready.
+    pub(crate) fn poll<F>(&self, cx: &mut Context<'_>, ready: F) -> Poll<()>
+    where
+        F: Fn() -> bool,
+    {
+        if ready() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.lock();
+        // Check again while holding the lock, so that a concurrent
+        // `notify_all` can't be missed.
+        if ready() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        if crate::chaos::spurious_wakeup() {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+
+    /// Wake every blocked thread and async task.
+    pub(crate) fn notify_all(&self) {
+        let wakers = core::mem::take(&mut *self.lock());
+        self.condvar.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// An object that can be waited on.
+pub(crate) trait Signal {
+    /// Returns true once waiting is complete.
+    fn is_ready(&self) -> bool;
+
+    /// The waiters list used to notify of readiness.
+    fn waiters(&self) -> &Waiters;
+}
+
+/// A future that resolves when a latch or event is ready.
+///
+/// This is returned by methods like [`OnceEvent::wait_async`].
+///
+/// [`OnceEvent::wait_async`]: crate::OnceEvent::wait_async
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Wait<'a> {
+    signal: &'a (dyn Signal + Sync),
+}
+
+impl<'a> Wait<'a> {
+    pub(crate) fn new(signal: &'a (dyn Signal + Sync)) -> Self {
+        Self { signal }
+    }
+}
+
+impl Future for Wait<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let signal = self.signal;
+        signal.waiters().poll(cx, || signal.is_ready())
+    }
+}
+
+/// A minimal executor for testing futures.
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(fut: F) -> F::Output {
+    use alloc::sync::Arc;
+    use core::task::{RawWaker, RawWakerVTable};
+    use std::thread::{self, Thread};
+
+    // The waker data is an `Arc<Thread>`, which is unparked on wake.
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let thread = Arc::from_raw(data as *const Thread);
+        core::mem::forget(Arc::clone(&thread));
+        core::mem::forget(thread);
+        RawWaker::new(data, &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        let thread = Arc::from_raw(data as *const Thread);
+        thread.unpark();
+    }
+    unsafe fn wake_by_ref(data: *const ()) {
+        (*(data as *const Thread)).unpark();
+    }
+    unsafe fn drop(data: *const ()) {
+        Arc::from_raw(data as *const Thread);
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    let data = Arc::into_raw(Arc::new(thread::current())) as *const ();
+    // SAFETY: the vtable functions above correctly manage the `Arc`.
+    let waker = unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = alloc::boxed::Box::pin(fut);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(val) => return val,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! This is the WeakBox implementation.
+
+use crate::MaybeBox;
+use alloc::sync::{Arc, Weak};
+
+/// `WeakBox` is a lazy-initialized weak reference.
+///
+/// A `WeakBox<T>` can have two possible states:
+/// * uninitialized: it does not contain a reference, and [`get`] will
+///   return `None`.
+/// * initialized: it contains a `Weak<T>`, and [`get`] will attempt to
+///   upgrade it, returning `Some(Arc<T>)` if the target is still alive.
+///
+/// To upgrade a `WeakBox` to the initialized state, call `lazy_init`.
+/// `lazy_init` does not require a mutable reference, which makes `WeakBox`
+/// a good fit for back-references in `Arc`-based data structures, where
+/// the child is usually shared before the parent exists.
+///
+/// # Panics
+///
+/// Attempting to `lazy_init` more than once will cause a panic.
+///
+/// [`get`]: WeakBox::get
+///
+/// # Examples
+/// ```
+/// use freezebox::WeakBox;
+/// use std::sync::Arc;
+///
+/// struct Child {
+///     parent: WeakBox<Parent>,
+/// }
+///
+/// struct Parent {
+///     child: Arc<Child>,
+/// }
+///
+/// let child = Arc::new(Child { parent: WeakBox::default() });
+/// let parent = Arc::new(Parent { child: child.clone() });
+/// child.parent.lazy_init(&parent);
+///
+/// assert!(Arc::ptr_eq(&child.parent.get().unwrap(), &parent));
+/// drop(parent);
+/// assert!(child.parent.get().is_none());
+/// ```
+pub struct WeakBox<T> {
+    inner: MaybeBox<Weak<T>>,
+}
+
+impl<T> WeakBox<T> {
+    /// Create a new `WeakBox` with optional initialization.
+    ///
+    /// To always create an uninitialized `WeakBox`, use
+    /// `WeakBox::default()`.
+    pub fn new(target: Option<&Arc<T>>) -> Self {
+        Self {
+            inner: MaybeBox::new(target.map(Arc::downgrade)),
+        }
+    }
+
+    /// Create a new `WeakBox` in `const` context
+    ///
+    /// This is the same as `WeakBox::default` except that it works in
+    /// const context, which is desirable for global `static` objects.
+    pub const fn const_default() -> Self {
+        Self {
+            inner: MaybeBox::const_default(),
+        }
+    }
+
+    /// Initialize a `WeakBox`.
+    ///
+    /// A weak reference to `target` will be stored; the `WeakBox` does
+    /// not keep `target` alive.
+    ///
+    /// # Panics
+    ///
+    /// `lazy_init` will panic if the `WeakBox` is already initialized.
+    pub fn lazy_init(&self, target: &Arc<T>) {
+        if let Err(weak) = self.inner.try_lazy_init(Arc::downgrade(target)) {
+            drop(weak);
+            panic_with_type!("lazy_init on already-initialized WeakBox", T);
+        }
+    }
+
+    /// Try to get a strong reference to the target.
+    ///
+    /// This will return `None` if the `WeakBox` is uninitialized, or if
+    /// the target has already been dropped.
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.inner.get().and_then(Weak::upgrade)
+    }
+
+    /// Test whether a `WeakBox` is initialized.
+    ///
+    /// Note that an initialized `WeakBox` may still return `None` from
+    /// [`get`][`WeakBox::get`], if the target has been dropped.
+    pub fn is_initialized(&self) -> bool {
+        self.inner.is_initialized()
+    }
+
+    /// Consume the `WeakBox` and return the stored weak reference.
+    pub fn into_inner(self) -> Option<Weak<T>> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T> Default for WeakBox<T> {
+    fn default() -> Self {
+        Self {
+            inner: MaybeBox::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeakBox;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn weakbox_test() {
+        let x = Arc::new("hello".to_string());
+        let y: WeakBox<String> = WeakBox::default();
+        assert!(!y.is_initialized());
+        assert!(y.get().is_none());
+        y.lazy_init(&x);
+        assert!(y.is_initialized());
+        assert_eq!(*y.get().unwrap(), "hello");
+
+        // The WeakBox must not keep its target alive.
+        assert_eq!(Arc::strong_count(&x), 1);
+        drop(x);
+        assert!(y.is_initialized());
+        assert!(y.get().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "lazy_init on already-initialized WeakBox")]
+    fn panic_double_init() {
+        let x = Arc::new("hello".to_string());
+        let y: WeakBox<String> = WeakBox::default();
+        y.lazy_init(&x);
+        y.lazy_init(&x);
+    }
+
+    #[test]
+    fn consume_test() {
+        let x = Arc::new("hello".to_string());
+        let y = WeakBox::new(Some(&x));
+        let weak = y.into_inner().unwrap();
+        assert!(Arc::ptr_eq(&weak.upgrade().unwrap(), &x));
+
+        let z = WeakBox::<String>::default();
+        assert!(z.into_inner().is_none());
+    }
+
+    #[test]
+    fn const_test() {
+        static X: WeakBox<String> = WeakBox::const_default();
+        assert!(X.get().is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Aliasing tests for the unsafe core of each container.
+//!
+//! These tests exercise the sequences most likely to violate the aliasing
+//! rules: reading while another thread initializes, holding references
+//! across other operations, and consuming a container after its readers
+//! are done. They pass under a normal test run, but they are most useful
+//! under Miri, which checks every access against the borrow model:
+//!
+//! ```text
+//! cargo +nightly miri test --all-features --test aliasing
+//! MIRIFLAGS=-Zmiri-tree-borrows cargo +nightly miri test --all-features --test aliasing
+//! ```
+//!
+//! Iteration counts are kept small, because Miri is slow.
+
+use freezebox::{FreezeArc, FreezeBox, FrozenRef, MaybeBox, TeardownBox};
+use std::sync::Arc;
+use std::thread;
+
+const READERS: usize = 3;
+
+#[test]
+fn freezebox_deref_during_init() {
+    let x = Arc::new(FreezeBox::<String>::default());
+    let handles: Vec<_> = (0..READERS)
+        .map(|_| {
+            let x = x.clone();
+            thread::spawn(move || {
+                while !x.is_initialized() {
+                    thread::yield_now();
+                }
+                let a: &String = &x;
+                let b: &String = &x;
+                assert_eq!(a, "hello");
+                assert!(std::ptr::eq(a, b));
+            })
+        })
+        .collect();
+    x.lazy_init("hello".to_string());
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn maybebox_get_during_init() {
+    let x = Arc::new(MaybeBox::<String>::default());
+    let handles: Vec<_> = (0..READERS)
+        .map(|_| {
+            let x = x.clone();
+            thread::spawn(move || loop {
+                if let Some(val) = x.get() {
+                    assert_eq!(val, "hello");
+                    break;
+                }
+                thread::yield_now();
+            })
+        })
+        .collect();
+    x.lazy_init("hello".to_string());
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn freezearc_deref_during_init() {
+    let x = Arc::new(FreezeArc::<String>::default());
+    let handles: Vec<_> = (0..READERS)
+        .map(|_| {
+            let x = x.clone();
+            thread::spawn(move || {
+                while !x.is_initialized() {
+                    thread::yield_now();
+                }
+                // A borrowed read and an owned clone may coexist.
+                let a: &String = &x;
+                let b = x.get_arc().unwrap();
+                assert_eq!(a, &*b);
+            })
+        })
+        .collect();
+    x.lazy_init(Arc::new("hello".to_string()));
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+#[test]
+fn reader_and_deref_interleaved() {
+    let x = FreezeBox::<Vec<u32>>::new(Some(vec![1, 2, 3]));
+    let reader = x.reader();
+    let direct: &Vec<u32> = &x;
+    let from_reader: &Vec<u32> = FrozenRef::get(&reader);
+    // Interleave reads through each path.
+    assert_eq!(direct[0], 1);
+    assert_eq!(reader[1], 2);
+    assert_eq!(from_reader[2], 3);
+    assert_eq!(direct.len(), reader.len());
+}
+
+#[test]
+fn failed_init_keeps_references_valid() {
+    let x = FreezeBox::<String>::new(Some("first".to_string()));
+    let y = MaybeBox::<String>::new(Some("first".to_string()));
+    let x_ref: &String = &x;
+    let y_ref: &String = y.get().unwrap();
+
+    // A failed double-init must not disturb the existing value.
+    let result = std::panic::catch_unwind(|| x.lazy_init("second".to_string()));
+    assert!(result.is_err());
+    let result = std::panic::catch_unwind(|| y.lazy_init("second".to_string()));
+    assert!(result.is_err());
+
+    assert_eq!(x_ref, "first");
+    assert_eq!(y_ref, "first");
+}
+
+#[test]
+fn into_inner_after_reads_end() {
+    let x = Arc::new(FreezeBox::<String>::new(Some("hello".to_string())));
+    let handles: Vec<_> = (0..READERS)
+        .map(|_| {
+            let x = x.clone();
+            thread::spawn(move || assert_eq!(x.len(), 5))
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    let x = Arc::try_unwrap(x).ok().unwrap();
+    assert_eq!(x.into_inner().unwrap(), "hello");
+
+    let y = MaybeBox::<String>::new(Some("hello".to_string()));
+    {
+        let r = y.get().unwrap();
+        assert_eq!(r, "hello");
+    }
+    assert_eq!(y.into_inner().unwrap(), "hello");
+
+    let z = FreezeArc::<String>::new(Some(Arc::new("hello".to_string())));
+    let clone = z.get_arc().unwrap();
+    {
+        let r: &String = &z;
+        assert_eq!(r, "hello");
+    }
+    let inner = z.into_inner().unwrap();
+    assert!(Arc::ptr_eq(&inner, &clone));
+}
+
+#[test]
+fn teardown_after_guards_end() {
+    let x = Arc::new(TeardownBox::<String>::default());
+    let key = x.teardown_key().unwrap();
+    x.lazy_init("hello".to_string());
+    let handles: Vec<_> = (0..READERS)
+        .map(|_| {
+            let x = x.clone();
+            thread::spawn(move || {
+                if let Some(guard) = x.get() {
+                    assert_eq!(*guard, "hello");
+                }
+            })
+        })
+        .collect();
+    x.teardown(key);
+    for h in handles {
+        h.join().unwrap();
+    }
+    assert!(x.get().is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sortedvec_references_survive_growth() {
+    use freezebox::FrozenSortedVec;
+
+    let x = FrozenSortedVec::<u32>::default();
+    let first = x.insert(50).unwrap();
+    // Grow the vector enough to force reallocation, inserting before and
+    // after the first value.
+    let refs: Vec<&u32> = (0..40).map(|i| x.insert(i * 3).unwrap()).collect();
+    assert_eq!(*first, 50);
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i as u32 * 3);
+    }
+    assert_eq!(x.len(), 41);
+    drop(refs);
+    assert_eq!(x.into_vec().len(), 41);
+}
@@ -0,0 +1,71 @@
+//! Tests for the `#[late_init]` attribute macro.
+
+#![cfg(feature = "macros")]
+
+use freezebox::late_init;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+#[late_init]
+#[derive(Default)]
+struct Resources {
+    #[late_init]
+    name: String,
+    #[late_init(maybe)]
+    pub(crate) nickname: String,
+    id: u32,
+}
+
+#[late_init]
+#[derive(Default)]
+struct Generic<'a, T: Debug, U = Vec<T>>
+where
+    T: Clone,
+{
+    #[late_init]
+    value: T,
+    #[late_init(maybe)]
+    list: U,
+    label: Option<&'a str>,
+    callback: Option<fn(u32) -> Result<T, String>>,
+}
+
+#[test]
+fn late_init_test() {
+    let res = Arc::new(Resources::default());
+    assert_eq!(res.nickname(), None);
+    assert_eq!(res.id, 0);
+
+    let res2 = res.clone();
+    res.set_name("Hello!".to_string());
+    res.set_nickname("Hi".to_string());
+    assert_eq!(res2.name(), "Hello!");
+    assert_eq!(res2.nickname().unwrap(), "Hi");
+}
+
+#[test]
+#[should_panic]
+fn uninitialized_get() {
+    let res = Resources::default();
+    res.name();
+}
+
+#[test]
+#[should_panic]
+fn double_set() {
+    let res = Resources::default();
+    res.set_name("a".to_string());
+    res.set_name("b".to_string());
+}
+
+#[test]
+fn generic_test() {
+    let x = Generic::<u32>::default();
+    assert!(x.list().is_none());
+    assert!(x.label.is_none());
+    assert!(x.callback.is_none());
+    x.set_value(5);
+    x.set_list(vec![1, 2]);
+    assert_eq!(*x.value(), 5);
+    assert_eq!(x.list().unwrap(), &[1, 2]);
+}
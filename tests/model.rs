@@ -0,0 +1,332 @@
+//! Differential tests comparing the containers against a reference model.
+//!
+//! Each test runs a random sequence of operations against a real container
+//! and against `Model`, a `Mutex<Option<T>>` that implements the same
+//! publish-once semantics in the most obvious way. Any observable
+//! difference is a bug.
+//!
+//! New container types should implement `Slot` and be added to the
+//! `all_slots!` list below.
+//!
+//! `StackFreeze` borrows its storage, so it can't be `Default` or `'static`
+//! on its own. It is tested through `LeakedStackFreeze`, which leaks a small
+//! heap slot for each instance.
+
+#[cfg(feature = "std")]
+use freezebox::MutexBox;
+use freezebox::{FreezeArc, FreezeBox, MaybeBox, StackFreeze};
+use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+
+/// The operations common to every publish-once container.
+trait Slot: Default + Send + Sync + 'static {
+    /// Initialize the slot; panics if it's already initialized.
+    fn init(&self, val: u32);
+
+    /// Read a copy of the contents.
+    fn read(&self) -> Option<u32>;
+
+    fn is_initialized(&self) -> bool;
+
+    fn into_inner(self) -> Option<u32>;
+}
+
+impl Slot for FreezeBox<u32> {
+    fn init(&self, val: u32) {
+        self.lazy_init(val);
+    }
+
+    fn read(&self) -> Option<u32> {
+        if self.is_initialized() {
+            Some(**self)
+        } else {
+            None
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        FreezeBox::is_initialized(self)
+    }
+
+    fn into_inner(self) -> Option<u32> {
+        FreezeBox::into_inner(self)
+    }
+}
+
+impl Slot for MaybeBox<u32> {
+    fn init(&self, val: u32) {
+        self.lazy_init(val);
+    }
+
+    fn read(&self) -> Option<u32> {
+        self.get().copied()
+    }
+
+    fn is_initialized(&self) -> bool {
+        MaybeBox::is_initialized(self)
+    }
+
+    fn into_inner(self) -> Option<u32> {
+        MaybeBox::into_inner(self)
+    }
+}
+
+impl Slot for FreezeArc<u32> {
+    fn init(&self, val: u32) {
+        self.lazy_init(Arc::new(val));
+    }
+
+    fn read(&self) -> Option<u32> {
+        self.get_arc().map(|arc| *arc)
+    }
+
+    fn is_initialized(&self) -> bool {
+        FreezeArc::is_initialized(self)
+    }
+
+    fn into_inner(self) -> Option<u32> {
+        FreezeArc::into_inner(self).map(|arc| *arc)
+    }
+}
+
+/// A `StackFreeze` whose slot is leaked, so that it is `Default` and
+/// `'static`.
+struct LeakedStackFreeze(StackFreeze<'static, u32>);
+
+impl Default for LeakedStackFreeze {
+    fn default() -> Self {
+        Self(StackFreeze::new(Box::leak(Box::new(MaybeUninit::uninit()))))
+    }
+}
+
+impl Slot for LeakedStackFreeze {
+    fn init(&self, val: u32) {
+        self.0.lazy_init(val);
+    }
+
+    fn read(&self) -> Option<u32> {
+        self.0.get().copied()
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.0.is_initialized()
+    }
+
+    fn into_inner(self) -> Option<u32> {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Slot for MutexBox<u32> {
+    fn init(&self, val: u32) {
+        self.lazy_init(val);
+    }
+
+    fn read(&self) -> Option<u32> {
+        self.lock().map(|guard| *guard)
+    }
+
+    fn is_initialized(&self) -> bool {
+        MutexBox::is_initialized(self)
+    }
+
+    fn into_inner(self) -> Option<u32> {
+        MutexBox::into_inner(self)
+    }
+}
+
+/// The reference model.
+#[derive(Default)]
+struct Model(Mutex<Option<u32>>);
+
+impl Model {
+    /// Returns true if this call initialized the model.
+    fn init(&self, val: u32) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        if inner.is_some() {
+            return false;
+        }
+        *inner = Some(val);
+        true
+    }
+
+    fn read(&self) -> Option<u32> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// A small deterministic PRNG (xorshift64), so failures are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Init(u32),
+    Read,
+    IsInitialized,
+}
+
+fn random_op(rng: &mut Rng) -> Op {
+    match rng.below(3) {
+        0 => Op::Init(rng.below(1000) as u32),
+        1 => Op::Read,
+        _ => Op::IsInitialized,
+    }
+}
+
+/// Silence the expected double-init panics.
+fn quiet_panics() {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| panic::set_hook(Box::new(|_| {})));
+}
+
+/// Returns true if `init` succeeded, false if it panicked.
+fn try_init<S: Slot>(slot: &S, val: u32) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(|| slot.init(val))).is_ok()
+}
+
+/// Run random single-threaded operation sequences, comparing every result.
+fn sequential<S: Slot>() {
+    quiet_panics();
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..200 {
+        let slot = S::default();
+        let model = Model::default();
+        for _ in 0..rng.below(10) {
+            let op = random_op(&mut rng);
+            match op {
+                Op::Init(val) => assert_eq!(try_init(&slot, val), model.init(val), "{:?}", op),
+                Op::Read => assert_eq!(slot.read(), model.read(), "{:?}", op),
+                Op::IsInitialized => {
+                    assert_eq!(slot.is_initialized(), model.read().is_some(), "{:?}", op)
+                }
+            }
+        }
+        assert_eq!(slot.into_inner(), model.read());
+    }
+}
+
+/// What one thread observed while running its operations.
+#[derive(Default)]
+struct History {
+    /// The value this thread successfully initialized, if any.
+    won: Option<u32>,
+    /// Every value this thread observed, in order.
+    reads: Vec<Option<u32>>,
+}
+
+/// Run random operation sequences on many threads at once.
+///
+/// Because the interleaving is not known in advance, the model is replayed
+/// afterwards: the winning `init` is applied first, which determines the
+/// only legal outcome for every other operation.
+fn concurrent<S: Slot>() {
+    quiet_panics();
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..20 {
+        let slot = Arc::new(S::default());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let ops: Vec<Op> = (0..20).map(|_| random_op(&mut rng)).collect();
+                let slot = Arc::clone(&slot);
+                thread::spawn(move || {
+                    let mut history = History::default();
+                    for op in ops {
+                        match op {
+                            Op::Init(val) => {
+                                if try_init(&*slot, val) {
+                                    assert!(history.won.is_none());
+                                    history.won = Some(val);
+                                }
+                            }
+                            Op::Read => history.reads.push(slot.read()),
+                            Op::IsInitialized => {
+                                // An initialized slot must stay readable.
+                                let init = slot.is_initialized();
+                                let val = slot.read();
+                                assert!(!init || val.is_some());
+                                history.reads.push(if init { val } else { None });
+                            }
+                        }
+                    }
+                    history
+                })
+            })
+            .collect();
+        let histories: Vec<History> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // Replay the model: at most one init may have won.
+        let model = Model::default();
+        let winners: Vec<u32> = histories.iter().filter_map(|h| h.won).collect();
+        assert!(winners.len() <= 1, "multiple winners: {:?}", winners);
+        if let Some(&winner) = winners.first() {
+            assert!(model.init(winner));
+        }
+        let published = model.read();
+
+        for history in &histories {
+            // Readers may see nothing, or the published value; once they
+            // see it, they must keep seeing it.
+            let mut seen = false;
+            for read in &history.reads {
+                match read {
+                    None => assert!(!seen, "value disappeared"),
+                    Some(val) => {
+                        assert_eq!(Some(*val), published);
+                        seen = true;
+                    }
+                }
+            }
+        }
+
+        let slot = Arc::try_unwrap(slot).ok().unwrap();
+        assert_eq!(slot.into_inner(), published);
+    }
+}
+
+macro_rules! all_slots {
+    ($($name:ident: $slot:ty,)*) => {
+        $(
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn sequential_model() {
+                    sequential::<$slot>();
+                }
+
+                #[test]
+                fn concurrent_model() {
+                    concurrent::<$slot>();
+                }
+            }
+        )*
+    };
+}
+
+all_slots! {
+    freeze_box: FreezeBox<u32>,
+    maybe_box: MaybeBox<u32>,
+    freeze_arc: FreezeArc<u32>,
+    stack_freeze: LeakedStackFreeze,
+}
+
+#[cfg(feature = "std")]
+all_slots! {
+    mutex_box: MutexBox<u32>,
+}
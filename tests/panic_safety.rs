@@ -0,0 +1,171 @@
+//! Panic-safety tests.
+//!
+//! These tests cover panics from user code called by the containers
+//! (initializer closures, `PartialEq` impls), as well as the containers'
+//! own double-init panics. In every case the container must stay usable,
+//! must not leak, and must not drop any value twice.
+
+use freezebox::{FreezeBox, MaybeBox};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+
+/// Silence the expected panics.
+fn quiet_panics() {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| panic::set_hook(Box::new(|_| {})));
+}
+
+/// Returns true if `f` panicked.
+fn panics<F: FnOnce() -> R, R>(f: F) -> bool {
+    quiet_panics();
+    panic::catch_unwind(AssertUnwindSafe(f)).is_err()
+}
+
+/// A value that counts how many times it has been dropped.
+#[derive(Debug)]
+struct Tracked {
+    id: u32,
+    drops: Arc<AtomicUsize>,
+}
+
+impl Tracked {
+    fn new(id: u32, drops: &Arc<AtomicUsize>) -> Self {
+        Self {
+            id,
+            drops: Arc::clone(drops),
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A `PartialEq` impl that panics, to test user code that runs while the
+/// container is already initialized.
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Self) -> bool {
+        if self.id == u32::MAX || other.id == u32::MAX {
+            panic!("comparison panicked");
+        }
+        self.id == other.id
+    }
+}
+
+#[test]
+fn double_init_drops_once() {
+    let drops = Arc::new(AtomicUsize::new(0));
+
+    let x = FreezeBox::<Tracked>::default();
+    x.lazy_init(Tracked::new(1, &drops));
+    assert!(panics(|| x.lazy_init(Tracked::new(2, &drops))));
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert_eq!(x.id, 1);
+    drop(x);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+
+    let y = MaybeBox::<Tracked>::default();
+    y.lazy_init(Tracked::new(1, &drops));
+    assert!(panics(|| y.lazy_init(Tracked::new(2, &drops))));
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+    assert_eq!(y.get().unwrap().id, 1);
+    drop(y);
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn idempotent_eq_panic() {
+    let drops = Arc::new(AtomicUsize::new(0));
+
+    let x = FreezeBox::<Tracked>::default();
+    x.lazy_init(Tracked::new(1, &drops));
+    assert!(panics(
+        || x.lazy_init_idempotent(Tracked::new(u32::MAX, &drops))
+    ));
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert_eq!(x.id, 1);
+
+    let y = MaybeBox::<Tracked>::default();
+    y.lazy_init(Tracked::new(1, &drops));
+    assert!(panics(
+        || y.lazy_init_idempotent(Tracked::new(u32::MAX, &drops))
+    ));
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+    assert_eq!(y.get().unwrap().id, 1);
+
+    drop((x, y));
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn tlsbox_closure_panic() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let x = freezebox::TlsBox::default();
+
+    // A panicking initializer leaves the slot empty.
+    assert!(panics(|| {
+        x.get_or(|| -> Tracked { panic!("initializer panicked") });
+    }));
+    assert!(!x.is_initialized());
+
+    // The slot can still be initialized afterwards.
+    assert_eq!(x.get_or(|| Tracked::new(1, &drops)).id, 1);
+    drop(x);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn get_or_init_closure_panic() {
+    let drops = Arc::new(AtomicUsize::new(0));
+
+    let x = FreezeBox::<Tracked>::default();
+    assert!(panics(|| x.get_or_init(|| panic!("initializer panicked"))));
+    assert!(!x.is_initialized());
+    assert_eq!(x.get_or_init(|| Tracked::new(1, &drops)).id, 1);
+    drop(x);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    let y = MaybeBox::<Tracked>::default();
+    assert!(panics(|| y.get_or_init(|| panic!("initializer panicked"))));
+    assert!(!y.is_initialized());
+    assert_eq!(y.get_or_init(|| Tracked::new(1, &drops)).id, 1);
+    drop(y);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn get_or_try_init_closure_panic() {
+    let drops = Arc::new(AtomicUsize::new(0));
+
+    let x = FreezeBox::<Tracked>::default();
+    assert!(panics(|| {
+        x.get_or_try_init(|| -> Result<Tracked, ()> { panic!("initializer panicked") })
+    }));
+    assert!(!x.is_initialized());
+    assert_eq!(
+        x.get_or_try_init(|| Ok::<_, ()>(Tracked::new(1, &drops)))
+            .unwrap()
+            .id,
+        1
+    );
+    drop(x);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    let y = MaybeBox::<Tracked>::default();
+    assert!(panics(|| {
+        y.get_or_try_init(|| -> Result<Tracked, ()> { panic!("initializer panicked") })
+    }));
+    assert!(!y.is_initialized());
+    assert_eq!(
+        y.get_or_try_init(|| Ok::<_, ()>(Tracked::new(1, &drops)))
+            .unwrap()
+            .id,
+        1
+    );
+    drop(y);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}